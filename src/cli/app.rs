@@ -60,6 +60,14 @@ impl App {
                     success.output.to_string_lossy().cyan(),
                     format!("{:.3}", success.seconds).cyan()
                 ));
+                for report in success.reports {
+                    Printer::blankln(1);
+                    Printer::echoln(format!("{}:", report.title));
+                    Printer::blankln(1);
+                    for item in report.items {
+                        Printer::echoln(format!(" - {}", item));
+                    }
+                }
                 AppOutput::Ok
             }
             Err(e) => {
@@ -79,6 +87,22 @@ impl App {
                 tips::merge_input_output()
             }
             MergeBuildError::UnparseableDepth(_) => tips::merge_depth(),
+            MergeBuildError::UnparseableOrientation(_) => tips::merge_orient(),
+            MergeBuildError::UnparseableBleed(_) => tips::merge_bleed(),
+            MergeBuildError::UnparseableBlankSensitivity(_) => tips::merge_blank_sensitivity(),
+            MergeBuildError::UnparseableDedupeMode(_) => tips::merge_dedupe_by(),
+            MergeBuildError::UnparseableSymlinkPolicy(_) => tips::merge_follow_symlinks(),
+            MergeBuildError::UnparseablePasswordEnv(_) => tips::password_usage(),
+            MergeBuildError::UnparseableBatesOption(..) => tips::bates_usage(),
+            MergeBuildError::UnparseableWatermarkOption(..) => tips::watermark_usage(),
+            MergeBuildError::UnparseableNUpOption(..) => tips::nup_usage(),
+            MergeBuildError::UnparseableBookletOption(..) => tips::booklet_usage(),
+            MergeBuildError::UnparseableTileOption(..) => tips::tile_usage(),
+            MergeBuildError::UnparseablePageSizeOption(..) => tips::page_size_usage(),
+            MergeBuildError::UnparseablePageBoxOption(..) => tips::page_box_usage(),
+            MergeBuildError::UnparseableEncryptOption(..) => tips::encrypt_usage(),
+            MergeBuildError::UnparseableSignOption(..) => tips::sign_usage(),
+            MergeBuildError::UnparseableLimitOption(..) => tips::resource_limits(),
             // allow this for future implementations
             #[allow(unreachable_patterns)]
             _ => todo!("Code must be implemented..."),
@@ -97,11 +121,21 @@ impl App {
             | MergeCheckError::OutputIsNotPdfFile(_) => tips::merge_input_output(),
             MergeCheckError::InputIsDirectoryReference(_)
             | MergeCheckError::OutputIsDirectoryReference(_) => tips::directory_references(),
-            MergeCheckError::InputRepetitionWithoutFlag(_) => tips::repetition_flag(),
+            MergeCheckError::InputRepetitionWithoutFlag(..) => tips::repetition_flag(),
             MergeCheckError::OutputAlreadyExists(_) => tips::override_flag(),
             MergeCheckError::CouldNotReadOrCheckFilePath(_) => tips::non_readable_file_path(),
             MergeCheckError::ParentOutputWithoutFlag(_) => tips::parent_flag_usage(),
             MergeCheckError::DepthNotSpecified => tips::depth_flag_usage(),
+            MergeCheckError::BatesLogIsNotCsvFile(_) => tips::bates_usage(),
+            MergeCheckError::WatermarkImageIsNotSupported(_) => tips::watermark_usage(),
+            MergeCheckError::StampIsNotPdfFile(_) => tips::stamp_usage(),
+            MergeCheckError::OrderIsNotFile(_) => tips::order_usage(),
+            MergeCheckError::PasswordFileIsNotFile(_) => tips::password_usage(),
+            MergeCheckError::KeystoreIsNotFile(_) => tips::sign_usage(),
+            MergeCheckError::KeptInputIsNotFirst(_) => tips::signed_inputs(),
+            MergeCheckError::RootIsNotDirectory(_) | MergeCheckError::PathIsOutsideRoot(_) => {
+                tips::root_usage()
+            }
         }
     }
 
@@ -117,17 +151,48 @@ impl App {
             MergeRunError::CouldNotReadEntry(_) => {
                 tips::non_readable_file_path();
             }
-            MergeRunError::PathRepetitionWithoutFlag(_) => {
+            MergeRunError::PathRepetitionWithoutFlag(..) => {
                 tips::repetition_flag();
             }
             MergeRunError::CouldNotLoadInput(_)
             | MergeRunError::RootPageNotFound
-            | MergeRunError::CatalogIsNone => {
+            | MergeRunError::CatalogIsNone
+            | MergeRunError::CouldNotEditMergedPages(_)
+            | MergeRunError::CouldNotLoadWatermarkImage(_) => {
                 tips::could_not_handle_pdf();
             }
-            MergeRunError::CouldNotSaveTheOutput(_) => {
+            MergeRunError::CouldNotSaveTheOutput(_) | MergeRunError::CouldNotWriteBatesLog(_) => {
                 tips::could_not_save_pdf();
             }
+            MergeRunError::CouldNotReadOrderFile(_)
+            | MergeRunError::InvalidPageReference(..)
+            | MergeRunError::EmptyPageOrder(_) => {
+                tips::order_usage();
+            }
+            MergeRunError::InputIsEncrypted(_)
+            | MergeRunError::WrongInputPassword(_)
+            | MergeRunError::PasswordEnvIsNotSet(_)
+            | MergeRunError::CouldNotReadPasswordFile(_) => {
+                tips::password_usage();
+            }
+            MergeRunError::CouldNotLoadKeystore(_) | MergeRunError::SignaturePageNotFound(_) => {
+                tips::sign_usage();
+            }
+            MergeRunError::InputIsSigned(_) => {
+                tips::refused_signed_input();
+            }
+            MergeRunError::KeptPathIsNotFirst(_) | MergeRunError::KeptInputIsEncrypted(_) => {
+                tips::signed_inputs();
+            }
+            MergeRunError::InputExceedsLimit(..) => {
+                tips::resource_limits();
+            }
+            MergeRunError::PathIsOutsideRoot(_) => {
+                tips::root_usage();
+            }
+            MergeRunError::BookletCreepTooLarge(..) => {
+                tips::booklet_usage();
+            }
             // This isn't necessarry since the function already stoped at this variant
             MergeRunError::EntryDoesNotExists(_) => {}
         }
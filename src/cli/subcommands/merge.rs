@@ -1,5 +1,5 @@
 use super::super::styles::APP_STYLE;
-use clap::Parser;
+use clap::{ArgGroup, Parser};
 
 /// Arguments for pdf merging
 #[derive(Parser, Default, Debug, PartialEq, Clone)]
@@ -8,10 +8,11 @@ use clap::Parser;
     author,
     version = env!("CARGO_PKG_VERSION"),
     about,
-    styles = APP_STYLE
+    styles = APP_STYLE,
+    group(ArgGroup::new("watermark").multiple(true).args(["watermark_text", "watermark_image"])),
 )]
 pub struct MergeArgs {
-    /// PDF files to be merged.
+    /// PDF files to be merged (`FILE#password-env=VAR` reads the password of an encrypted one).
     #[arg(
         long,
         short,
@@ -33,6 +34,10 @@ pub struct MergeArgs {
     #[arg(long)]
     pub allow_repetition: bool,
 
+    /// How repeated inputs are detected: `path`, `inode` or `hash` (default: inode).
+    #[arg(long, value_name = "MODE")]
+    pub dedupe_by: Option<String>,
+
     /// Merge PDFs until reaches the `N` directory layer (use `*` to infinity).
     #[arg(long, short, value_name = "N|*", required = false)]
     pub depth: Option<String>,
@@ -40,6 +45,290 @@ pub struct MergeArgs {
     /// Creates parent directories of the output file (if they don't exists).
     #[arg(long, short)]
     pub parent: bool,
+
+    /// Stamp Bates numbers on every page (using the given prefix).
+    #[arg(long, value_name = "PREFIX")]
+    pub bates: Option<String>,
+
+    /// Text placed after the Bates number.
+    #[arg(long, value_name = "SUFFIX", requires = "bates")]
+    pub bates_suffix: Option<String>,
+
+    /// First Bates number (default: 1).
+    #[arg(long, value_name = "N", requires = "bates")]
+    pub bates_start: Option<String>,
+
+    /// Minimum number of Bates digits, zero padded (default: 6).
+    #[arg(long, value_name = "N", requires = "bates")]
+    pub bates_digits: Option<String>,
+
+    /// Where to place the Bates stamp (`top|bottom`-`left|center|right`, default: bottom-right).
+    #[arg(long, value_name = "POSITION", requires = "bates")]
+    pub bates_position: Option<String>,
+
+    /// Write a CSV log mapping each Bates range to its source file.
+    #[arg(long, value_name = "FILE", requires = "bates")]
+    pub bates_log: Option<String>,
+
+    /// Draw a text watermark at the center of the pages.
+    #[arg(long, value_name = "TEXT")]
+    pub watermark_text: Option<String>,
+
+    /// Draw an image (PNG/JPEG) watermark at the center of the pages.
+    #[arg(long, value_name = "FILE")]
+    pub watermark_image: Option<String>,
+
+    /// Watermark text font size (default: 48).
+    #[arg(long, value_name = "PT", requires = "watermark_text")]
+    pub watermark_size: Option<String>,
+
+    /// Watermark counterclockwise rotation in degrees (default: 45).
+    #[arg(
+        long,
+        value_name = "DEG",
+        requires = "watermark",
+        allow_hyphen_values = true
+    )]
+    pub watermark_rotation: Option<String>,
+
+    /// Watermark text color (default: 808080).
+    #[arg(long, value_name = "RRGGBB", requires = "watermark_text")]
+    pub watermark_color: Option<String>,
+
+    /// Watermark opacity, from 0 to 1 (default: 0.3).
+    #[arg(long, value_name = "N", requires = "watermark")]
+    pub watermark_opacity: Option<String>,
+
+    /// Pages to be watermarked, like `1-3,5,8-` (default: all).
+    #[arg(long, value_name = "PAGES", requires = "watermark")]
+    pub watermark_pages: Option<String>,
+
+    /// Draw the watermark under the page content (instead of over it).
+    #[arg(long, requires = "watermark")]
+    pub watermark_under: bool,
+
+    /// Draw the pages of a PDF file under the merged pages (like a letterhead).
+    #[arg(long, value_name = "FILE")]
+    pub underlay: Option<String>,
+
+    /// Draw the pages of a PDF file over the merged pages.
+    #[arg(long, value_name = "FILE")]
+    pub overlay: Option<String>,
+
+    /// Place `R` rows by `C` columns of merged pages on each output sheet.
+    #[arg(long, value_name = "RxC")]
+    pub nup: Option<String>,
+
+    /// N-up sheet size (`a3`, `a4`, `a5`, `letter`, `legal`, `tabloid` or `WxH[mm|in|pt]`).
+    #[arg(long, value_name = "SIZE", requires = "nup")]
+    pub nup_paper: Option<String>,
+
+    /// Space between the N-up grid cells (in points).
+    #[arg(long, value_name = "PT", requires = "nup")]
+    pub nup_gutter: Option<String>,
+
+    /// Draw a border around each N-up placed page.
+    #[arg(long, requires = "nup")]
+    pub nup_border: bool,
+
+    /// Impose the merged pages as a booklet (two pages per sheet side, for saddle-stitch printing).
+    #[arg(long, conflicts_with = "nup")]
+    pub booklet: bool,
+
+    /// Booklet sheet size (`a3`, `a4`, `a5`, `letter`, `legal`, `tabloid` or `WxH[mm|in|pt]`).
+    #[arg(long, value_name = "SIZE", requires = "booklet")]
+    pub booklet_paper: Option<String>,
+
+    /// Space between the two pages of a booklet sheet side (in points).
+    #[arg(long, value_name = "PT", requires = "booklet")]
+    pub booklet_gutter: Option<String>,
+
+    /// Shift the booklet pages towards the fold by `PT` points per nested sheet.
+    #[arg(long, value_name = "PT", requires = "booklet")]
+    pub booklet_creep: Option<String>,
+
+    /// Split the booklet into signatures of `N` sheets.
+    #[arg(long, value_name = "N", requires = "booklet")]
+    pub booklet_signature: Option<String>,
+
+    /// Split each merged page into overlapping tiles of the given paper size (at real size).
+    #[arg(long, value_name = "SIZE", conflicts_with_all = ["nup", "booklet"])]
+    pub tile: Option<String>,
+
+    /// How much adjacent tiles overlap (in points).
+    #[arg(long, value_name = "PT", requires = "tile")]
+    pub tile_overlap: Option<String>,
+
+    /// Tile sheet margin, where the crop marks and labels are drawn (in points).
+    #[arg(long, value_name = "PT", requires = "tile")]
+    pub tile_margin: Option<String>,
+
+    /// Resize every merged page to a paper size (`a3`, `a4`, `a5`, `letter`, `legal`, `tabloid`
+    /// or `WxH[mm|in|pt]`), keeping its orientation.
+    #[arg(long, value_name = "SIZE")]
+    pub page_size: Option<String>,
+
+    /// How to scale the content of the resized pages (`fit`, `fill` or `none`, default: fit).
+    #[arg(long, value_name = "MODE", requires = "page_size")]
+    pub page_scale: Option<String>,
+
+    /// Rotate the pages that don't match the given orientation (`portrait` or `landscape`).
+    #[arg(long, value_name = "ORIENTATION")]
+    pub orient: Option<String>,
+
+    /// Crop the merged pages by margins (`ALL`, `V,H` or `T,R,B,L`) or to a box (`box:X0,Y0,X1,Y1`).
+    #[arg(long, value_name = "MARGINS|BOX")]
+    pub crop: Option<String>,
+
+    /// Add a binding margin (in points), on the left of odd pages and the right of even ones.
+    #[arg(long, value_name = "PT")]
+    pub add_margin: Option<String>,
+
+    /// Enlarge the pages by a bleed area, drawing crop and registration marks around them.
+    #[arg(long)]
+    pub print_marks: bool,
+
+    /// Bleed size in points (default: 9).
+    #[arg(long, value_name = "PT", requires = "print_marks")]
+    pub bleed: Option<String>,
+
+    /// Remove the pages that draw nothing visible (like blank scanned backsides).
+    #[arg(long)]
+    pub drop_blank: bool,
+
+    /// How light a color can be to be considered white, from 0 to 1 (default: 0).
+    #[arg(long, value_name = "N", requires = "drop_blank")]
+    pub blank_sensitivity: Option<String>,
+
+    /// Remove the pages identical to a previous one (same content, resources and annotations).
+    #[arg(long)]
+    pub dedupe_pages: bool,
+
+    /// Reverse the final page sequence.
+    #[arg(long)]
+    pub reverse: bool,
+
+    /// Build the page sequence from a file listing page references (`file.pdf:N`, one per line).
+    #[arg(long, value_name = "FILE")]
+    pub order: Option<String>,
+
+    /// Draw form fields and annotations into the page content, removing them (links are kept).
+    #[arg(long)]
+    pub flatten: bool,
+
+    /// File listing passwords (one per line) to try on the encrypted inputs.
+    #[arg(long, value_name = "FILE")]
+    pub password_file: Option<String>,
+
+    /// Ask for the password of the encrypted inputs (when no other password works).
+    #[arg(long)]
+    pub password_prompt: bool,
+
+    /// Encrypt the output (the passwords are read from `env:VAR` or `file:PATH`).
+    #[arg(long)]
+    pub encrypt: bool,
+
+    /// Encryption algorithm: `aes-256` or `aes-128` (default: aes-256).
+    #[arg(long, value_name = "ALGORITHM", requires = "encrypt")]
+    pub encrypt_algorithm: Option<String>,
+
+    /// Password needed to open the output (default: none).
+    #[arg(long, value_name = "SOURCE", requires = "encrypt")]
+    pub user_password: Option<String>,
+
+    /// Password needed to change the permissions (default: a random one).
+    #[arg(long, value_name = "SOURCE", requires = "encrypt")]
+    pub owner_password: Option<String>,
+
+    /// Permissions not granted: `print`, `copy`, `modify`, `annotate`, `fill` or `assemble`.
+    #[arg(long, value_name = "LIST", requires = "encrypt")]
+    pub deny: Option<String>,
+
+    /// Strip JavaScript, open/additional actions, launch/submit/import actions, embedded files,
+    /// RichMedia and XFA from the inputs.
+    #[arg(long)]
+    pub sanitize: bool,
+
+    /// Strip Info dictionaries, XMP packets, document IDs, annotation authors, thumbnails and
+    /// application private data from the output.
+    #[arg(long)]
+    pub anonymize: bool,
+
+    /// Fail when an input is digitally signed (merging invalidates its signature).
+    #[arg(long)]
+    pub refuse_signed: bool,
+
+    /// Keep the signature of the first input, appending the other ones as an incremental update.
+    #[arg(
+        long,
+        value_name = "FIRST",
+        conflicts_with_all = [
+            "encrypt", "order", "reverse", "nup", "booklet", "tile", "watermark", "underlay",
+            "overlay", "page_size", "orient", "crop", "add_margin", "print_marks", "dedupe_pages",
+            "flatten", "sanitize", "anonymize",
+        ],
+    )]
+    pub keep_signature_of: Option<String>,
+
+    /// Sign the output with the key of a PKCS#12 keystore (`.p12`/`.pfx`).
+    #[arg(long, value_name = "FILE", conflicts_with = "encrypt")]
+    pub sign: Option<String>,
+
+    /// Environment variable holding the keystore passphrase (default: empty passphrase).
+    #[arg(long, value_name = "VAR", requires = "sign")]
+    pub sign_password_env: Option<String>,
+
+    /// Why the output was signed.
+    #[arg(long, value_name = "TEXT", requires = "sign")]
+    pub sign_reason: Option<String>,
+
+    /// Where the output was signed.
+    #[arg(long, value_name = "TEXT", requires = "sign")]
+    pub sign_location: Option<String>,
+
+    /// Draw the signature box at: `top-left`, `top-center`, `top-right`, `bottom-left`,
+    /// `bottom-center` or `bottom-right` (default: invisible signature).
+    #[arg(long, value_name = "POSITION", requires = "sign")]
+    pub sign_visible: Option<String>,
+
+    /// Page holding the signature (default: the last one).
+    #[arg(long, value_name = "N", requires = "sign")]
+    pub sign_page: Option<String>,
+
+    /// Fail when an input has more objects than this.
+    #[arg(long, value_name = "N")]
+    pub max_objects: Option<String>,
+
+    /// Fail when an input stream is larger than this once decompressed (in bytes).
+    #[arg(long, value_name = "BYTES")]
+    pub max_stream_bytes: Option<String>,
+
+    /// Fail when an input has more pages than this.
+    #[arg(long, value_name = "N")]
+    pub max_pages: Option<String>,
+
+    /// Fail when an input file is larger than this (in bytes).
+    #[arg(long, value_name = "BYTES")]
+    pub max_input_size: Option<String>,
+
+    /// Fail when the arrays/dictionaries of an input are nested deeper than this (default: 64,
+    /// at most 128).
+    #[arg(long, value_name = "N")]
+    pub max_nesting: Option<String>,
+
+    /// Sandbox directory every input and output must resolve inside (following symlinks), allowing
+    /// `..` references within it.
+    #[arg(long, value_name = "DIR")]
+    pub root: Option<String>,
+
+    /// Symlinks followed within the input directories: `never`, `files` or `all` (default: all).
+    #[arg(long, value_name = "POLICY")]
+    pub follow_symlinks: Option<String>,
+
+    /// Merge the files generated by earlier runs found within the input directories (the output
+    /// itself is still left out).
+    #[arg(long)]
+    pub keep_generated: bool,
 }
 
 impl MergeArgs {
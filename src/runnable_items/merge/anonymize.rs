@@ -0,0 +1,43 @@
+use lopdf::{Document, Object};
+
+/// Keys holding metadata (XMP packets), thumbnails or application private data, removed from
+/// every dictionary.
+const PRIVATE_KEYS: [&[u8]; 4] = [b"Metadata", b"PieceInfo", b"LastModified", b"Thumb"];
+
+/// Removes the private keys within `object`, and the author (`/T`) of the annotations that aren't
+/// form fields (where `/T` is the field name).
+fn strip(object: &mut Object) {
+    let dict = match object {
+        Object::Dictionary(dict) => dict,
+        Object::Stream(stream) => &mut stream.dict,
+        Object::Array(array) => {
+            array.iter_mut().for_each(strip);
+            return;
+        }
+        _ => return,
+    };
+    for key in PRIVATE_KEYS {
+        dict.remove(key);
+    }
+    let is_annot = dict.has(b"Rect") && dict.has(b"Subtype");
+    let is_field = dict.has(b"FT")
+        || dict
+            .get(b"Subtype")
+            .and_then(Object::as_name)
+            .is_ok_and(|s| s == b"Widget");
+    if is_annot && !is_field {
+        dict.remove(b"T");
+    }
+    dict.iter_mut().for_each(|(_, value)| strip(value));
+}
+
+/// Strips everything that identifies the original authors or their tools from the merged
+/// document: Info dictionaries, XMP packets, `/PieceInfo` (and other application private data),
+/// the document ID, annotation authors and embedded thumbnails.
+pub fn apply(doc: &mut Document) {
+    doc.trailer.remove(b"Info");
+    doc.trailer.remove(b"ID");
+    doc.objects.values_mut().for_each(strip);
+    // the inputs Info dictionaries and XMP streams are no longer referenced
+    doc.prune_objects();
+}
@@ -0,0 +1,136 @@
+use super::{
+    MergeRunError,
+    page::{self, Anchor, Geometry, PageOrigin},
+};
+use lopdf::{
+    Document, Object,
+    content::{Content, Operation},
+    dictionary,
+};
+use std::{fs, path::PathBuf};
+
+/// Font resource name used by the stamps (unlikely to collide with the page's own fonts).
+const FONT_NAME: &[u8] = b"PdmersBates";
+/// Stamp font size.
+const FONT_SIZE: f32 = 10.0;
+/// Courier glyph width (all glyphs have the same width, which makes the stamp width easy to
+/// compute).
+const GLYPH_WIDTH: f32 = 0.6;
+/// Distance between the stamp and the page edges.
+const MARGIN: f32 = 18.0;
+
+/// Bates numbering options (`ACME000001`, `ACME000002`, ...).
+#[derive(Debug, PartialEq)]
+pub struct Bates {
+    /// Text placed before the number.
+    pub prefix: String,
+    /// Text placed after the number.
+    pub suffix: String,
+    /// Number of the first page.
+    pub start: u64,
+    /// Minimum number of digits (zero padded).
+    pub digits: usize,
+    /// Where to place the stamp.
+    pub anchor: Anchor,
+    /// Where to write the CSV log (Bates range -> source file).
+    pub log: Option<PathBuf>,
+}
+
+impl Bates {
+    /// Bates label for the page at `index` (starting at `0`).
+    pub fn label(&self, index: usize) -> String {
+        format!(
+            "{}{:0width$}{}",
+            self.prefix,
+            self.start + index as u64,
+            self.suffix,
+            width = self.digits
+        )
+    }
+
+    /// Stamps every page of the document with its Bates label, starting at the label of the page
+    /// at `first` (the pages of an input kept by `--keep-signature-of` take the first labels,
+    /// though they can't be stamped).
+    pub fn stamp(&self, doc: &mut Document, first: usize) -> Result<(), MergeRunError> {
+        let err = |_| MergeRunError::CouldNotEditMergedPages("bates".into());
+        let font = doc.add_object(dictionary! {
+            "Type" => "Font",
+            "Subtype" => "Type1",
+            "BaseFont" => "Courier",
+        });
+        for (index, page_id) in doc.page_iter().collect::<Vec<_>>().into_iter().enumerate() {
+            let label = self.label(first + index);
+            let geometry = Geometry::of(doc, page_id);
+            let width = label.chars().count() as f32 * GLYPH_WIDTH * FONT_SIZE;
+            let (x, y) = self
+                .anchor
+                .place(geometry.visual_size(), (width, FONT_SIZE), MARGIN);
+            let content = Content {
+                operations: vec![
+                    Operation::new("q", vec![]),
+                    Operation::new(
+                        "cm",
+                        geometry
+                            .visual_matrix()
+                            .into_iter()
+                            .map(Object::Real)
+                            .collect(),
+                    ),
+                    Operation::new("g", vec![0.into()]),
+                    Operation::new("BT", vec![]),
+                    Operation::new("Tf", vec![Object::Name(FONT_NAME.into()), FONT_SIZE.into()]),
+                    Operation::new("Td", vec![x.into(), y.into()]),
+                    Operation::new("Tj", vec![Object::string_literal(label)]),
+                    Operation::new("ET", vec![]),
+                    Operation::new("Q", vec![]),
+                ],
+            }
+            .encode()
+            .map_err(err)?;
+            page::add_resource(doc, page_id, b"Font", FONT_NAME, Object::Reference(font))
+                .map_err(err)?;
+            page::stamp_over(doc, page_id, content).map_err(err)?;
+        }
+        Ok(())
+    }
+
+    /// Writes the CSV log (one row for each contiguous page range of a source file), if any.
+    pub fn write_log(&self, origins: &[PageOrigin]) -> Result<(), MergeRunError> {
+        let Some(path) = &self.log else {
+            return Ok(());
+        };
+        let mut csv = String::from("begin_bates,end_bates,source_file,first_page,last_page\n");
+        let mut begin = 0;
+        for (index, origin) in origins.iter().enumerate() {
+            let next = origins.get(index + 1);
+            if next.is_some_and(|n| n.file == origin.file && n.page == origin.page + 1) {
+                continue;
+            }
+            csv.push_str(&format!(
+                "{},{},{},{},{}\n",
+                self.label(begin),
+                self.label(index),
+                csv_field(&origin.file.to_string_lossy()),
+                origins[begin].page,
+                origin.page
+            ));
+            begin = index + 1;
+        }
+        fs::write(path, csv).map_err(|_| MergeRunError::CouldNotWriteBatesLog(path.clone()))
+    }
+}
+
+/// Whether the text can be stamped (Courier is drawn with its standard encoding, so only the
+/// printable ASCII characters are the same in every viewer).
+pub fn is_printable(text: &str) -> bool {
+    text.chars().all(|c| c == ' ' || c.is_ascii_graphic())
+}
+
+/// Quotes a CSV field when needed.
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.into()
+    }
+}
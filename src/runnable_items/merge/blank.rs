@@ -0,0 +1,203 @@
+use super::page;
+use lopdf::{Dictionary, Document, Object, ObjectId, content::Content};
+
+/// How deep nested form XObjects are inspected (deeper forms are considered visible).
+const MAX_FORM_DEPTH: usize = 8;
+
+/// Whitespace bytes within text strings.
+const WHITESPACE: &[u8] = b" \t\r\n\0";
+
+/// Blank pages detection options.
+#[derive(Debug, PartialEq)]
+pub struct Blank {
+    /// How light a color must be to be considered white (from `0`, only pure white, to `1`, any
+    /// color).
+    pub sensitivity: f32,
+}
+
+/// Graphics state values that matter to tell if something is visible.
+#[derive(Clone, Copy)]
+struct State {
+    /// Fill color lightness (from `0` to `1`).
+    fill: f32,
+    /// Stroke color lightness (from `0` to `1`).
+    stroke: f32,
+    /// Text rendering mode.
+    render: i64,
+}
+
+/// Lightness of a gray, RGB or CMYK color (unknown colors are black).
+fn lightness(operands: &[Object]) -> f32 {
+    let values = operands
+        .iter()
+        .map(|o| o.as_float().ok())
+        .collect::<Option<Vec<_>>>()
+        .unwrap_or_default();
+    match values.as_slice() {
+        [gray] => *gray,
+        [r, g, b] => 0.3 * r + 0.59 * g + 0.11 * b,
+        [c, m, y, k] => 1.0 - (0.3 * c + 0.59 * m + 0.11 * y + k).min(1.0),
+        _ => 0.0,
+    }
+}
+
+/// Whether a text string shows something other than whitespace.
+fn shows_text(operand: &Object) -> bool {
+    match operand {
+        Object::String(bytes, _) => bytes.iter().any(|b| !WHITESPACE.contains(b)),
+        Object::Array(items) => items.iter().any(shows_text),
+        _ => false,
+    }
+}
+
+impl Blank {
+    /// Tells if the page draws nothing visible: empty content, whitespace only text or white
+    /// fills only. Pages with annotations (other than links) aren't blank.
+    pub fn is_blank(&self, doc: &Document, page_id: ObjectId) -> bool {
+        let Some(page) = page::inherited_page(doc, page_id) else {
+            return false;
+        };
+        let annotated = page
+            .get(b"Annots")
+            .and_then(|a| doc.dereference(a))
+            .and_then(|(_, a)| a.as_array())
+            .is_ok_and(|annots| {
+                annots.iter().any(|a| {
+                    doc.dereference(a)
+                        .and_then(|(_, a)| a.as_dict())
+                        .and_then(|a| a.get(b"Subtype"))
+                        .and_then(Object::as_name)
+                        .map_or(true, |subtype| subtype != b"Link")
+                })
+            });
+        let resources = page
+            .get(b"Resources")
+            .and_then(|r| doc.dereference(r))
+            .and_then(|(_, r)| r.as_dict())
+            .cloned()
+            .unwrap_or_default();
+        !annotated && !self.draws(doc, &page::content(doc, page_id), &resources, 0)
+    }
+
+    /// Whether the color lightness is considered white.
+    fn is_white(&self, lightness: f32) -> bool {
+        lightness >= 1.0 - self.sensitivity
+    }
+
+    /// Whether the content draws something visible.
+    fn draws(&self, doc: &Document, content: &[u8], resources: &Dictionary, depth: usize) -> bool {
+        let Ok(content) = Content::decode(content) else {
+            return true;
+        };
+        let mut state = State {
+            fill: 0.0,
+            stroke: 0.0,
+            render: 0,
+        };
+        let mut saved = Vec::new();
+        for operation in content.operations {
+            let operands = operation.operands.as_slice();
+            let (fills, strokes) = match operation.operator.as_str() {
+                "q" => {
+                    saved.push(state);
+                    continue;
+                }
+                "Q" => {
+                    state = saved.pop().unwrap_or(state);
+                    continue;
+                }
+                "g" | "rg" | "k" | "sc" | "scn" => {
+                    state.fill = lightness(operands);
+                    continue;
+                }
+                "G" | "RG" | "K" | "SC" | "SCN" => {
+                    state.stroke = lightness(operands);
+                    continue;
+                }
+                "cs" => {
+                    state.fill = 0.0;
+                    continue;
+                }
+                "CS" => {
+                    state.stroke = 0.0;
+                    continue;
+                }
+                "Tr" => {
+                    state.render = operands.first().and_then(|o| o.as_i64().ok()).unwrap_or(0);
+                    continue;
+                }
+                "Tj" | "TJ" | "'" | "\"" => {
+                    if !operands.last().is_some_and(shows_text) {
+                        continue;
+                    }
+                    match state.render {
+                        0 | 4 => (true, false),
+                        1 | 5 => (false, true),
+                        2 | 6 => (true, true),
+                        _ => continue,
+                    }
+                }
+                "f" | "F" | "f*" => (true, false),
+                "S" | "s" => (false, true),
+                "B" | "B*" | "b" | "b*" => (true, true),
+                "sh" | "BI" | "EI" => return true,
+                "Do" => {
+                    if self.draws_xobject(doc, operands, resources, depth) {
+                        return true;
+                    }
+                    continue;
+                }
+                _ => continue,
+            };
+            if (fills && !self.is_white(state.fill)) || (strokes && !self.is_white(state.stroke)) {
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Whether the XObject painted by a `Do` operation is visible (images and unknown XObjects
+    /// always are).
+    fn draws_xobject(
+        &self,
+        doc: &Document,
+        operands: &[Object],
+        resources: &Dictionary,
+        depth: usize,
+    ) -> bool {
+        let xobject = operands
+            .first()
+            .and_then(|name| name.as_name().ok())
+            .and_then(|name| {
+                resources
+                    .get(b"XObject")
+                    .and_then(|x| doc.dereference(x))
+                    .and_then(|(_, x)| x.as_dict())
+                    .and_then(|x| x.get(name))
+                    .and_then(|x| doc.dereference(x))
+                    .and_then(|(_, x)| x.as_stream())
+                    .ok()
+            });
+        let Some(stream) = xobject else {
+            return true;
+        };
+        let is_form = stream
+            .dict
+            .get(b"Subtype")
+            .and_then(Object::as_name)
+            .is_ok_and(|subtype| subtype == b"Form");
+        if !is_form || depth >= MAX_FORM_DEPTH {
+            return true;
+        }
+        let form_resources = stream
+            .dict
+            .get(b"Resources")
+            .and_then(|r| doc.dereference(r))
+            .and_then(|(_, r)| r.as_dict())
+            .unwrap_or(resources);
+        let content = stream
+            .decompressed_content()
+            .unwrap_or_else(|_| stream.content.clone());
+        self.draws(doc, &content, form_resources, depth + 1)
+    }
+}
@@ -0,0 +1,93 @@
+use super::{
+    MergeRunError,
+    nup::add_sheet,
+    page::{self, PageOrigin},
+    paper::PaperSize,
+};
+use lopdf::Document;
+use std::collections::BTreeMap;
+
+/// Booklet imposition options (saddle-stitch printing).
+#[derive(Debug, PartialEq)]
+pub struct Booklet {
+    /// Sheet size (used in the landscape orientation, two pages side by side).
+    pub paper: PaperSize,
+    /// Space between the two pages of a sheet side (at the fold).
+    pub gutter: f32,
+    /// How much the pages are shifted towards the fold for each sheet nested in the signature.
+    pub creep: f32,
+    /// Sheets per signature (a single signature when [`None`]).
+    pub signature: Option<u32>,
+}
+
+/// Largest creep keeping the innermost of the nested `sheets` pages from being shifted past the
+/// fold (half the `gutter` on each side of it).
+pub fn max_creep(gutter: f32, sheets: usize) -> f32 {
+    match sheets {
+        0 | 1 => f32::INFINITY,
+        n => gutter / 2.0 / (n - 1) as f32,
+    }
+}
+
+impl Booklet {
+    /// Reorders and imposes the document pages two per sheet side, so folding the printed
+    /// (duplex) sheets gives a booklet. The pages are padded with blanks to a multiple of 4.
+    ///
+    /// For a 4 pages signature the sides are `4,1` (front) and `2,3` (back). Each sheet side
+    /// origin is the origin of its lowest page (blanks take the last page origin).
+    ///
+    /// Fails when the creep shifts the pages of the innermost sheet past the fold.
+    pub fn apply(
+        &self,
+        doc: &mut Document,
+        origins: &mut Vec<PageOrigin>,
+    ) -> Result<(), MergeRunError> {
+        let err = |_| MergeRunError::CouldNotEditMergedPages("booklet".into());
+        let mut pages = doc.page_iter().map(Some).collect::<Vec<_>>();
+        if pages.is_empty() {
+            return Ok(());
+        }
+        pages.resize(pages.len().next_multiple_of(4), None);
+        let signature = self
+            .signature
+            .map_or(pages.len(), |sheets| sheets as usize * 4);
+        let sheets = signature.min(pages.len()) / 4;
+        if self.creep > max_creep(self.gutter, sheets) {
+            return Err(MergeRunError::BookletCreepTooLarge(self.creep, sheets));
+        }
+        let (width, height) = self.paper.oriented(true);
+        let cell_w = (width - self.gutter) / 2.0;
+
+        let mut sheets = Vec::new();
+        let mut sheets_origins = Vec::new();
+        let mut replaced = BTreeMap::new();
+        for (chunk_index, chunk) in pages.chunks(signature).enumerate() {
+            let n = chunk.len();
+            for sheet in 0..n / 4 {
+                let shift = self.creep * sheet as f32;
+                let sides = [
+                    (n - 1 - 2 * sheet, 2 * sheet),
+                    (2 * sheet + 1, n - 2 - 2 * sheet),
+                ];
+                for (left, right) in sides {
+                    let cells = [(left, shift), (right, cell_w + self.gutter - shift)]
+                        .into_iter()
+                        .filter_map(|(index, x)| {
+                            chunk[index].map(|id| (id, [x, 0.0, cell_w, height]))
+                        })
+                        .collect::<Vec<_>>();
+                    let side = add_sheet(doc, (width, height), &cells, false).map_err(err)?;
+                    cells.iter().for_each(|(id, _)| {
+                        replaced.insert(*id, side);
+                    });
+                    let first = (chunk_index * signature + left.min(right)).min(origins.len() - 1);
+                    sheets_origins.push(origins[first].clone());
+                    sheets.push(side);
+                }
+            }
+        }
+        page::set_pages(doc, sheets, &replaced).map_err(err)?;
+        *origins = sheets_origins;
+        Ok(())
+    }
+}
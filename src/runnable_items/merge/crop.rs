@@ -0,0 +1,103 @@
+use super::{
+    MergeRunError, Report,
+    page::{self, Geometry, PageOrigin},
+};
+use colored::Colorize;
+use lopdf::{Document, Object};
+use std::str::FromStr;
+
+/// How the pages are cropped.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Crop {
+    /// Margins (`top`, `right`, `bottom`, `left`) removed from the visible page box, as the
+    /// page is displayed.
+    Margins([f32; 4]),
+    /// The new page box (`x0`, `y0`, `x1`, `y1`, in the page user space).
+    Box([f32; 4]),
+}
+
+/// Parses a list of comma separated non negative numbers.
+fn numbers(s: &str) -> Result<Vec<f32>, ()> {
+    s.split(',')
+        .map(|n| n.trim().parse::<f32>().ok().filter(|n| *n >= 0.0).ok_or(()))
+        .collect()
+}
+
+impl FromStr for Crop {
+    type Err = ();
+    /// Parses margins like CSS does (`ALL`, `VERTICAL,HORIZONTAL` or `TOP,RIGHT,BOTTOM,LEFT`) or
+    /// an explicit box (`box:X0,Y0,X1,Y1`).
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(rect) = s.strip_prefix("box:") {
+            return match numbers(rect)?.as_slice() {
+                [x0, y0, x1, y1] if x0 < x1 && y0 < y1 => Ok(Self::Box([*x0, *y0, *x1, *y1])),
+                _ => Err(()),
+            };
+        }
+        match numbers(s)?.as_slice() {
+            [all] => Ok(Self::Margins([*all; 4])),
+            [v, h] => Ok(Self::Margins([*v, *h, *v, *h])),
+            [t, r, b, l] => Ok(Self::Margins([*t, *r, *b, *l])),
+            _ => Err(()),
+        }
+    }
+}
+
+impl Crop {
+    /// Sets the `CropBox` and `TrimBox` of every page. Pages where the margins don't fit are
+    /// kept as they are, returning their report (with their size).
+    pub fn apply(
+        &self,
+        doc: &mut Document,
+        origins: &[PageOrigin],
+    ) -> Result<Report, MergeRunError> {
+        let err = |_| MergeRunError::CouldNotEditMergedPages("crop".into());
+        let mut items = Vec::new();
+        for (page_id, origin) in doc.page_iter().collect::<Vec<_>>().into_iter().zip(origins) {
+            let geometry = Geometry::of(doc, page_id);
+            let rect = match self {
+                Self::Box(rect) => *rect,
+                Self::Margins([top, right, bottom, left]) => {
+                    let (width, height) = geometry.visual_size();
+                    if left + right >= width || top + bottom >= height {
+                        items.push(format!(
+                            "{origin}: {} x {} pt",
+                            width.round().to_string().cyan(),
+                            height.round().to_string().cyan()
+                        ));
+                        continue;
+                    }
+                    page::transform_rect(
+                        geometry.visual_matrix(),
+                        [*left, *bottom, width - right, height - top],
+                    )
+                }
+            };
+            let rect = rect.into_iter().map(Object::Real).collect::<Vec<_>>();
+            let page = doc.get_dictionary_mut(page_id).map_err(err)?;
+            page.set("CropBox", rect.clone());
+            page.set("TrimBox", rect);
+        }
+        Ok(Report {
+            title: "Pages left uncropped (margins larger than the page)".into(),
+            items,
+        })
+    }
+}
+
+/// Grows the pages by `margin` (binding gutter) moving their content, so the gutter is on the
+/// left of odd pages and on the right of even ones.
+pub fn add_margin(doc: &mut Document, margin: f32) -> Result<(), MergeRunError> {
+    let err = |_| MergeRunError::CouldNotEditMergedPages("margin".into());
+    for (number, page_id) in doc.get_pages() {
+        let geometry = Geometry::of(doc, page_id);
+        let (width, height) = geometry.visual_size();
+        let shift = match number % 2 {
+            1 => margin,
+            _ => 0.0,
+        };
+        let matrix = page::multiply(geometry.user_matrix(), [1.0, 0.0, 0.0, 1.0, shift, 0.0]);
+        page::transform(doc, page_id, matrix, [0.0, 0.0, width + margin, height]).map_err(err)?;
+    }
+    Ok(())
+}
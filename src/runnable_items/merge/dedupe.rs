@@ -0,0 +1,152 @@
+use super::page;
+use lopdf::{Document, Object, ObjectId};
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+
+/// Page attributes (besides the content and the annotations) that change how the page looks.
+const APPEARANCE: [&[u8]; 4] = [b"Resources", b"MediaBox", b"CropBox", b"Rotate"];
+
+/// Hashes the pages of a document by what they show (object ids don't matter, so identical pages
+/// from different files get the same digest).
+pub struct PageHasher<'a> {
+    doc: &'a Document,
+    /// Digests of the already hashed objects (a `None` one is being hashed, so it's a cycle).
+    memo: HashMap<ObjectId, Option<[u8; 32]>>,
+}
+
+impl<'a> PageHasher<'a> {
+    pub fn new(doc: &'a Document) -> Self {
+        Self {
+            doc,
+            memo: HashMap::new(),
+        }
+    }
+
+    /// Digest of the page decoded content, its resources, its boxes and its annotations.
+    pub fn page(&mut self, page_id: ObjectId) -> [u8; 32] {
+        let mut hasher = Sha256::new();
+        hasher.update(page::content(self.doc, page_id));
+        if let Some(page) = page::inherited_page(self.doc, page_id) {
+            for key in APPEARANCE {
+                hasher.update(key);
+                if let Ok(object) = page.get(key) {
+                    self.object(object, &mut hasher);
+                }
+            }
+            hasher.update(b"Annots");
+            if let Ok(annots) = page.get(b"Annots") {
+                self.annotations(annots, &mut hasher);
+            }
+        }
+        hasher.finalize().into()
+    }
+
+    /// Feeds the page annotations: their dictionaries (but the `P` reference back to the page)
+    /// and the field value of the widgets, inherited from the parent fields when it isn't set (so
+    /// filled forms sharing a template don't look the same).
+    fn annotations(&mut self, annots: &Object, hasher: &mut Sha256) {
+        let annots = match self.doc.dereference(annots) {
+            Ok((_, Object::Array(annots))) => annots.clone(),
+            _ => return,
+        };
+        hasher.update((annots.len() as u64).to_le_bytes());
+        for annot in &annots {
+            let Ok((_, Object::Dictionary(annot))) = self.doc.dereference(annot) else {
+                hasher.update(b"n");
+                continue;
+            };
+            let mut annot = annot.clone();
+            annot.remove(b"P");
+            self.dictionary(&annot, hasher);
+            hasher.update(b"V");
+            let mut field = Some(annot);
+            let mut seen = HashSet::new();
+            while let Some(node) = field.take() {
+                if let Ok(value) = node.get(b"V") {
+                    self.object(value, hasher);
+                    break;
+                }
+                field = node
+                    .get(b"Parent")
+                    .and_then(Object::as_reference)
+                    .ok()
+                    .filter(|id| seen.insert(*id))
+                    .and_then(|id| self.doc.get_dictionary(id).ok())
+                    .cloned();
+            }
+        }
+    }
+
+    /// Digest of a referenced object.
+    fn reference(&mut self, id: ObjectId) -> [u8; 32] {
+        match self.memo.get(&id) {
+            Some(Some(digest)) => return *digest,
+            Some(None) => return [0; 32],
+            None => {}
+        }
+        self.memo.insert(id, None);
+        let mut hasher = Sha256::new();
+        if let Ok(object) = self.doc.get_object(id) {
+            self.object(object, &mut hasher);
+        }
+        let digest = hasher.finalize().into();
+        self.memo.insert(id, Some(digest));
+        digest
+    }
+
+    /// Feeds the object into the hasher (references are replaced by their digests and streams
+    /// are decoded).
+    fn object(&mut self, object: &Object, hasher: &mut Sha256) {
+        let sized = |hasher: &mut Sha256, tag: &[u8], bytes: &[u8]| {
+            hasher.update(tag);
+            hasher.update((bytes.len() as u64).to_le_bytes());
+            hasher.update(bytes);
+        };
+        match object {
+            Object::Null => hasher.update(b"n"),
+            Object::Boolean(b) => sized(hasher, b"b", &[*b as u8]),
+            Object::Integer(i) => sized(hasher, b"i", &i.to_le_bytes()),
+            Object::Real(r) => sized(hasher, b"r", &r.to_le_bytes()),
+            Object::Name(name) => sized(hasher, b"/", name),
+            Object::String(bytes, _) => sized(hasher, b"s", bytes),
+            Object::Reference(id) => {
+                let digest = self.reference(*id);
+                sized(hasher, b"R", &digest);
+            }
+            Object::Array(items) => {
+                sized(hasher, b"[", &(items.len() as u64).to_le_bytes());
+                items.iter().for_each(|item| self.object(item, hasher));
+            }
+            Object::Dictionary(dict) => self.dictionary(dict, hasher),
+            Object::Stream(stream) => {
+                self.dictionary(&stream.dict, hasher);
+                let content = stream
+                    .decompressed_content()
+                    .unwrap_or_else(|_| stream.content.clone());
+                sized(hasher, b"stream", &content);
+            }
+        }
+    }
+
+    /// Feeds the dictionary entries (sorted by key, `Parent` and stream `Length`/`Filter` keys
+    /// are skipped since they don't change what is shown).
+    fn dictionary(&mut self, dict: &lopdf::Dictionary, hasher: &mut Sha256) {
+        let mut entries = dict
+            .iter()
+            .filter(|(key, _)| {
+                !matches!(
+                    key.as_slice(),
+                    b"Parent" | b"Length" | b"Filter" | b"DecodeParms"
+                )
+            })
+            .collect::<Vec<_>>();
+        entries.sort_by_key(|(key, _)| *key);
+        hasher.update(b"<");
+        hasher.update((entries.len() as u64).to_le_bytes());
+        for (key, value) in entries {
+            hasher.update((key.len() as u64).to_le_bytes());
+            hasher.update(key);
+            self.object(value, hasher);
+        }
+    }
+}
@@ -0,0 +1,147 @@
+use super::MergeRunError;
+use lopdf::{
+    Document, EncryptionState, EncryptionVersion, Object, Permissions, StringFormat,
+    encryption::crypt_filters::{Aes128CryptFilter, Aes256CryptFilter, CryptFilter},
+};
+use std::{collections::BTreeMap, env, fs, path::PathBuf, str::FromStr, sync::Arc};
+
+/// Crypt filter name used by the encrypted output.
+const FILTER_NAME: &[u8] = b"StdCF";
+
+/// Output encryption algorithm.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum Algorithm {
+    Aes128,
+    #[default]
+    Aes256,
+}
+
+impl FromStr for Algorithm {
+    type Err = ();
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "aes-128" | "aes128" => Ok(Self::Aes128),
+            "aes-256" | "aes256" => Ok(Self::Aes256),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Where a password is read from (never the command line itself).
+#[derive(Debug, Clone, PartialEq)]
+pub enum Secret {
+    /// Environment variable holding the password.
+    Env(String),
+    /// File whose first line is the password.
+    File(PathBuf),
+}
+
+impl FromStr for Secret {
+    type Err = ();
+    /// Parses `env:VAR` or `file:PATH`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once(':') {
+            Some(("env", var)) if !var.trim().is_empty() => Ok(Self::Env(var.trim().into())),
+            Some(("file", path)) if !path.trim().is_empty() => Ok(Self::File(path.trim().into())),
+            _ => Err(()),
+        }
+    }
+}
+
+impl Secret {
+    /// Reads the password.
+    fn read(&self) -> Result<String, MergeRunError> {
+        match self {
+            Self::Env(var) => {
+                env::var(var).map_err(|_| MergeRunError::PasswordEnvIsNotSet(var.into()))
+            }
+            Self::File(path) => fs::read_to_string(path)
+                .map(|text| text.lines().next().unwrap_or_default().to_string())
+                .map_err(|_| MergeRunError::CouldNotReadPasswordFile(path.into())),
+        }
+    }
+}
+
+/// Parses a comma separated list of denied permissions (`print`, `copy`, `modify`, `annotate`,
+/// `fill` or `assemble`).
+pub fn denied(s: &str) -> Result<Permissions, ()> {
+    s.split(',')
+        .map(|p| match p.trim().to_lowercase().as_str() {
+            "print" => Ok(Permissions::PRINTABLE | Permissions::PRINTABLE_IN_HIGH_QUALITY),
+            "copy" => Ok(Permissions::COPYABLE),
+            "modify" => Ok(Permissions::MODIFIABLE),
+            "annotate" => Ok(Permissions::ANNOTABLE),
+            "fill" => Ok(Permissions::FILLABLE),
+            "assemble" => Ok(Permissions::ASSEMBLABLE),
+            _ => Err(()),
+        })
+        .collect()
+}
+
+/// Output encryption options.
+#[derive(Debug, PartialEq)]
+pub struct Encryption {
+    pub algorithm: Algorithm,
+    /// Password needed to open the output (none when [`None`]).
+    pub user: Option<Secret>,
+    /// Password needed to change the permissions (a random one when [`None`]).
+    pub owner: Option<Secret>,
+    /// Permissions the output doesn't grant (without the owner password).
+    pub denied: Permissions,
+}
+
+/// Random bytes (for the file identifier and the encryption keys).
+fn random<const N: usize>() -> Result<[u8; N], MergeRunError> {
+    let mut bytes = [0; N];
+    getrandom::fill(&mut bytes)
+        .map_err(|_| MergeRunError::CouldNotEditMergedPages("encrypt".into()))?;
+    Ok(bytes)
+}
+
+impl Encryption {
+    /// Encrypts the document (it must be the last step before saving, since every string and
+    /// stream gets encrypted).
+    pub fn apply(&self, doc: &mut Document) -> Result<(), MergeRunError> {
+        let err = |_| MergeRunError::CouldNotEditMergedPages("encrypt".into());
+        let user = self.user.as_ref().map_or(Ok(String::new()), Secret::read)?;
+        let owner = match &self.owner {
+            Some(owner) => owner.read()?,
+            None => random::<16>()?.iter().map(|b| format!("{b:02x}")).collect(),
+        };
+        if !doc.trailer.has(b"ID") {
+            let id = Object::String(random::<16>()?.to_vec(), StringFormat::Hexadecimal);
+            doc.trailer.set("ID", vec![id.clone(), id]);
+        }
+        let permissions = Permissions::all() - self.denied;
+        let key = random::<32>()?;
+        let version = match self.algorithm {
+            Algorithm::Aes128 => EncryptionVersion::V4 {
+                document: doc,
+                encrypt_metadata: true,
+                crypt_filters: filters(Arc::new(Aes128CryptFilter)),
+                stream_filter: FILTER_NAME.to_vec(),
+                string_filter: FILTER_NAME.to_vec(),
+                owner_password: &owner,
+                user_password: &user,
+                permissions,
+            },
+            Algorithm::Aes256 => EncryptionVersion::V5 {
+                encrypt_metadata: true,
+                crypt_filters: filters(Arc::new(Aes256CryptFilter)),
+                file_encryption_key: &key,
+                stream_filter: FILTER_NAME.to_vec(),
+                string_filter: FILTER_NAME.to_vec(),
+                owner_password: &owner,
+                user_password: &user,
+                permissions,
+            },
+        };
+        let state = EncryptionState::try_from(version).map_err(err)?;
+        doc.encrypt(&state).map_err(err)
+    }
+}
+
+/// Crypt filters map with the single filter used for both strings and streams.
+fn filters(filter: Arc<dyn CryptFilter>) -> BTreeMap<Vec<u8>, Arc<dyn CryptFilter>> {
+    BTreeMap::from([(FILTER_NAME.to_vec(), filter)])
+}
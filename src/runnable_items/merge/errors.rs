@@ -1,3 +1,4 @@
+use super::limits::Limit;
 use crate::utils::print::PrintableItem;
 use colored::Colorize;
 use std::path::PathBuf;
@@ -11,6 +12,38 @@ pub enum MergeBuildError {
     OutputIsEmpty,
     /// When the depth input is "unparseable".
     UnparseableDepth(String),
+    /// When the orientation input is "unparseable".
+    UnparseableOrientation(String),
+    /// When the bleed input is "unparseable".
+    UnparseableBleed(String),
+    /// When the blank sensitivity input is "unparseable".
+    UnparseableBlankSensitivity(String),
+    /// When the dedupe mode input is "unparseable".
+    UnparseableDedupeMode(String),
+    /// When an input password suffix (`#password-env=VAR`) has no variable name.
+    UnparseablePasswordEnv(String),
+    /// When a bates option value (flag, value) is "unparseable".
+    UnparseableBatesOption(String, String),
+    /// When a watermark option value (flag, value) is "unparseable".
+    UnparseableWatermarkOption(String, String),
+    /// When a N-up option value (flag, value) is "unparseable".
+    UnparseableNUpOption(String, String),
+    /// When a booklet option value (flag, value) is "unparseable".
+    UnparseableBookletOption(String, String),
+    /// When a tile option value (flag, value) is "unparseable".
+    UnparseableTileOption(String, String),
+    /// When a page size option value (flag, value) is "unparseable".
+    UnparseablePageSizeOption(String, String),
+    /// When a page box option value (flag, value) is "unparseable".
+    UnparseablePageBoxOption(String, String),
+    /// When an encryption option value (flag, value) is "unparseable".
+    UnparseableEncryptOption(String, String),
+    /// When a signing option value (flag, value) is "unparseable".
+    UnparseableSignOption(String, String),
+    /// When a resource limit value (flag, value) is "unparseable".
+    UnparseableLimitOption(String, String),
+    /// When the symlink policy is "unparseable".
+    UnparseableSymlinkPolicy(String),
 }
 
 impl std::fmt::Display for MergeBuildError {
@@ -23,6 +56,40 @@ impl std::fmt::Display for MergeBuildError {
                 MergeBuildError::OutputIsEmpty => "output path wasn't provided".into(),
                 MergeBuildError::UnparseableDepth(d) =>
                     format!("couldn't parse the `depth` value (`{}`)", d.bright_cyan()),
+                MergeBuildError::UnparseableOrientation(o) =>
+                    format!("couldn't parse the `orient` value (`{}`)", o.bright_cyan()),
+                MergeBuildError::UnparseableBleed(b) =>
+                    format!("couldn't parse the `bleed` value (`{}`)", b.bright_cyan()),
+                MergeBuildError::UnparseableBlankSensitivity(s) => format!(
+                    "couldn't parse the `blank-sensitivity` value (`{}`)",
+                    s.bright_cyan()
+                ),
+                MergeBuildError::UnparseableDedupeMode(m) => format!(
+                    "couldn't parse the `dedupe-by` value (`{}`)",
+                    m.bright_cyan()
+                ),
+                MergeBuildError::UnparseableSymlinkPolicy(p) => format!(
+                    "couldn't parse the `follow-symlinks` value (`{}`)",
+                    p.bright_cyan()
+                ),
+                MergeBuildError::UnparseablePasswordEnv(i) => format!(
+                    "couldn't parse the input password variable (`{}`)",
+                    i.bright_cyan()
+                ),
+                MergeBuildError::UnparseableBatesOption(flag, v)
+                | MergeBuildError::UnparseableWatermarkOption(flag, v)
+                | MergeBuildError::UnparseableNUpOption(flag, v)
+                | MergeBuildError::UnparseableBookletOption(flag, v)
+                | MergeBuildError::UnparseableTileOption(flag, v)
+                | MergeBuildError::UnparseablePageSizeOption(flag, v)
+                | MergeBuildError::UnparseablePageBoxOption(flag, v)
+                | MergeBuildError::UnparseableEncryptOption(flag, v)
+                | MergeBuildError::UnparseableSignOption(flag, v)
+                | MergeBuildError::UnparseableLimitOption(flag, v) => format!(
+                    "couldn't parse the `{}` value (`{}`)",
+                    flag.bright_green(),
+                    v.bright_cyan()
+                ),
             }
         )
     }
@@ -45,8 +112,8 @@ pub enum MergeCheckError {
     InputIsNotPdfFile(PathBuf),
     /// When a non `.pdf` file is passed as output.
     OutputIsNotPdfFile(PathBuf),
-    /// When trying to repeat an input file path without `--allow-repetition` flag.
-    InputRepetitionWithoutFlag(PathBuf),
+    /// When trying to repeat an input file (first, repeated) without `--allow-repetition` flag.
+    InputRepetitionWithoutFlag(PathBuf, PathBuf),
     /// When passing an already existing output path without the `--override` flag.
     OutputAlreadyExists(PathBuf),
     /// When the file path couldn't be read (due to permissions or time-out reasons).
@@ -55,6 +122,24 @@ pub enum MergeCheckError {
     ParentOutputWithoutFlag(PathBuf),
     /// When passing a directory as input without specifying a depth.
     DepthNotSpecified,
+    /// When a non `.csv` file is passed as bates log.
+    BatesLogIsNotCsvFile(PathBuf),
+    /// When the watermark image isn't a PNG/JPEG file.
+    WatermarkImageIsNotSupported(PathBuf),
+    /// When the underlay/overlay file isn't an existing `.pdf` file.
+    StampIsNotPdfFile(PathBuf),
+    /// When the page order file doesn't exist.
+    OrderIsNotFile(PathBuf),
+    /// When the password file doesn't exist.
+    PasswordFileIsNotFile(PathBuf),
+    /// When the signing keystore (`.p12`/`.pfx`) doesn't exist.
+    KeystoreIsNotFile(PathBuf),
+    /// When the input whose signature is kept isn't the first one.
+    KeptInputIsNotFirst(PathBuf),
+    /// When the sandbox root isn't an existing directory.
+    RootIsNotDirectory(PathBuf),
+    /// When an input/output path resolves outside the sandbox root.
+    PathIsOutsideRoot(PathBuf),
 }
 
 impl std::fmt::Display for MergeCheckError {
@@ -82,9 +167,10 @@ impl std::fmt::Display for MergeCheckError {
                         p.to_string_lossy().bright_cyan()
                     )
                 }
-                Self::InputRepetitionWithoutFlag(p) => {
+                Self::InputRepetitionWithoutFlag(first, p) => {
                     format!(
-                        "you passed the same input more than once (`{}`)",
+                        "you passed the same input more than once (`{}` and `{}`)",
+                        first.to_string_lossy().bright_cyan(),
                         p.to_string_lossy().bright_cyan()
                     )
                 }
@@ -109,6 +195,60 @@ impl std::fmt::Display for MergeCheckError {
                 Self::DepthNotSpecified => {
                     "depth wasn't specified".into()
                 }
+                Self::WatermarkImageIsNotSupported(p) => {
+                    format!(
+                        "watermark image must be a png/jpeg file (`{}`)",
+                        p.to_string_lossy().bright_cyan()
+                    )
+                }
+                Self::StampIsNotPdfFile(p) => {
+                    format!(
+                        "underlay/overlay must be an existing pdf file (`{}`)",
+                        p.to_string_lossy().bright_cyan()
+                    )
+                }
+                Self::OrderIsNotFile(p) => {
+                    format!(
+                        "page order file doesn't exists (`{}`)",
+                        p.to_string_lossy().bright_cyan()
+                    )
+                }
+                Self::PasswordFileIsNotFile(p) => {
+                    format!(
+                        "password file doesn't exists (`{}`)",
+                        p.to_string_lossy().bright_cyan()
+                    )
+                }
+                Self::KeystoreIsNotFile(p) => {
+                    format!(
+                        "signing keystore must be an existing p12/pfx file (`{}`)",
+                        p.to_string_lossy().bright_cyan()
+                    )
+                }
+                Self::KeptInputIsNotFirst(p) => {
+                    format!(
+                        "the input whose signature is kept must be the first one (`{}`)",
+                        p.to_string_lossy().bright_cyan()
+                    )
+                }
+                Self::BatesLogIsNotCsvFile(p) => {
+                    format!(
+                        "bates log must be a csv file (`{}`)",
+                        p.to_string_lossy().bright_cyan()
+                    )
+                }
+                Self::RootIsNotDirectory(p) => {
+                    format!(
+                        "sandbox root must be an existing directory (`{}`)",
+                        p.to_string_lossy().bright_cyan()
+                    )
+                }
+                Self::PathIsOutsideRoot(p) => {
+                    format!(
+                        "path resolves outside the sandbox root (`{}`)",
+                        p.to_string_lossy().bright_cyan()
+                    )
+                }
             }
         )
     }
@@ -117,12 +257,12 @@ impl std::fmt::Display for MergeCheckError {
 impl PrintableItem for MergeCheckError {}
 
 /// Errors that can occur when running the merge actions.
-#[derive(Clone)]
+#[derive(Clone, PartialEq, Debug)]
 pub enum MergeRunError {
     /// When entry reading returns [`Err`].
     CouldNotReadEntry(PathBuf),
-    /// When there's implicit repetition without `--allow-repetition` flag.
-    PathRepetitionWithoutFlag(PathBuf),
+    /// When there's implicit repetition (first, repeated) without `--allow-repetition` flag.
+    PathRepetitionWithoutFlag(PathBuf, PathBuf),
     /// When the searching entry doesn't exists.
     EntryDoesNotExists(PathBuf),
     /// When the PDF file can't be read (possibly [`lopdf`] inner bug, idk).
@@ -133,6 +273,43 @@ pub enum MergeRunError {
     RootPageNotFound,
     /// Self explanatory.
     CatalogIsNone,
+    /// When a post merging step (named by the string) fails to edit the merged pages.
+    CouldNotEditMergedPages(String),
+    /// When the bates CSV log can't be written.
+    CouldNotWriteBatesLog(PathBuf),
+    /// When the watermark image can't be loaded.
+    CouldNotLoadWatermarkImage(PathBuf),
+    /// When the page order file can't be read.
+    CouldNotReadOrderFile(PathBuf),
+    /// When a page order line (number, reference) doesn't point to a single merged page.
+    InvalidPageReference(usize, String),
+    /// When the page order file doesn't reference any page.
+    EmptyPageOrder(PathBuf),
+    /// When an input is encrypted and no password was given for it.
+    InputIsEncrypted(PathBuf),
+    /// When the given password doesn't decrypt the input.
+    WrongInputPassword(PathBuf),
+    /// When the environment variable holding an input password isn't set.
+    PasswordEnvIsNotSet(String),
+    /// When the password file can't be read.
+    CouldNotReadPasswordFile(PathBuf),
+    /// When the signing keystore can't be opened (wrong passphrase or no RSA key inside).
+    CouldNotLoadKeystore(PathBuf),
+    /// When the signature page (number) isn't within the output.
+    SignaturePageNotFound(u32),
+    /// When a digitally signed input is merged with `--refuse-signed`.
+    InputIsSigned(PathBuf),
+    /// When the path whose signature is kept isn't the first one found.
+    KeptPathIsNotFirst(PathBuf),
+    /// When the input whose signature is kept is encrypted.
+    KeptInputIsEncrypted(PathBuf),
+    /// When an input exceeds a resource limit.
+    InputExceedsLimit(PathBuf, Limit),
+    /// When an entry found within an input directory resolves outside the sandbox root.
+    PathIsOutsideRoot(PathBuf),
+    /// When the booklet creep (points) shifts the pages of the innermost of the nested sheets
+    /// (count) past the fold.
+    BookletCreepTooLarge(f32, usize),
 }
 
 impl std::fmt::Display for MergeRunError {
@@ -145,8 +322,9 @@ impl std::fmt::Display for MergeRunError {
                     "failed to read file/dir entry (`{}`)",
                     p.to_string_lossy().bright_cyan()
                 ),
-                Self::PathRepetitionWithoutFlag(p) => format!(
-                    "entry repetition without allowing (`{}`)",
+                Self::PathRepetitionWithoutFlag(first, p) => format!(
+                    "entry repetition without allowing (`{}` and `{}`)",
+                    first.to_string_lossy().bright_cyan(),
                     p.to_string_lossy().bright_cyan()
                 ),
                 Self::EntryDoesNotExists(p) => format!(
@@ -163,6 +341,82 @@ impl std::fmt::Display for MergeRunError {
                 ),
                 Self::RootPageNotFound => "root page not found".into(),
                 Self::CatalogIsNone => "catalog is none".into(),
+                Self::CouldNotEditMergedPages(step) => format!(
+                    "couldn't apply `{}` to the merged pages",
+                    step.bright_green()
+                ),
+                Self::CouldNotLoadWatermarkImage(p) => format!(
+                    "couldn't load the watermark image (`{}`)",
+                    p.to_string_lossy().bright_cyan()
+                ),
+                Self::CouldNotWriteBatesLog(p) => format!(
+                    "bates log couldn't be written (`{}`)",
+                    p.to_string_lossy().bright_cyan()
+                ),
+                Self::CouldNotReadOrderFile(p) => format!(
+                    "page order file couldn't be read (`{}`)",
+                    p.to_string_lossy().bright_cyan()
+                ),
+                Self::InvalidPageReference(line, r) => format!(
+                    "invalid page reference at line {} (`{}`)",
+                    line,
+                    r.bright_cyan()
+                ),
+                Self::EmptyPageOrder(p) => format!(
+                    "page order file doesn't reference any page (`{}`)",
+                    p.to_string_lossy().bright_cyan()
+                ),
+                Self::InputIsEncrypted(p) => format!(
+                    "input is encrypted and no password was given (`{}`)",
+                    p.to_string_lossy().bright_cyan()
+                ),
+                Self::WrongInputPassword(p) => format!(
+                    "wrong password for the encrypted input (`{}`)",
+                    p.to_string_lossy().bright_cyan()
+                ),
+                Self::PasswordEnvIsNotSet(v) => format!(
+                    "password environment variable isn't set (`{}`)",
+                    v.bright_cyan()
+                ),
+                Self::CouldNotReadPasswordFile(p) => format!(
+                    "password file couldn't be read (`{}`)",
+                    p.to_string_lossy().bright_cyan()
+                ),
+                Self::CouldNotLoadKeystore(p) => format!(
+                    "signing keystore couldn't be opened (`{}`)",
+                    p.to_string_lossy().bright_cyan()
+                ),
+                Self::InputIsSigned(p) => format!(
+                    "input is digitally signed (`{}`)",
+                    p.to_string_lossy().bright_cyan()
+                ),
+                Self::KeptPathIsNotFirst(p) => format!(
+                    "the input whose signature is kept must be the first one (`{}`)",
+                    p.to_string_lossy().bright_cyan()
+                ),
+                Self::KeptInputIsEncrypted(p) => format!(
+                    "the input whose signature is kept can't be encrypted (`{}`)",
+                    p.to_string_lossy().bright_cyan()
+                ),
+                Self::SignaturePageNotFound(n) => format!(
+                    "signature page isn't within the output (`{}`)",
+                    n.to_string().bright_cyan()
+                ),
+                Self::PathIsOutsideRoot(p) => format!(
+                    "entry resolves outside the sandbox root (`{}`)",
+                    p.to_string_lossy().bright_cyan()
+                ),
+                Self::BookletCreepTooLarge(creep, sheets) => format!(
+                    "booklet creep shifts the inner pages past the fold (`{}` pt over {} sheets)",
+                    creep.to_string().bright_cyan(),
+                    sheets
+                ),
+                Self::InputExceedsLimit(p, limit) => format!(
+                    "input exceeds the `{}` limit of {} (`{}`)",
+                    limit.flag().bright_green(),
+                    limit.value().to_string().bright_cyan(),
+                    p.to_string_lossy().bright_cyan()
+                ),
             }
         )
     }
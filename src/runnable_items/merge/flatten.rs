@@ -0,0 +1,176 @@
+use super::{
+    MergeRunError, Report,
+    page::{self, PageOrigin},
+};
+use colored::Colorize;
+use lopdf::{
+    Dictionary, Document, Object, ObjectId,
+    content::{Content, Operation},
+    decode_text_string,
+};
+use std::collections::HashSet;
+
+/// Annotation flags (`Hidden` and `NoView`) that keep the annotation from being displayed.
+const HIDDEN: i64 = 2 | 32;
+/// Form XObject resource name prefix of the flattened appearances.
+const APPEARANCE_NAME: &str = "PdmersAnnot";
+
+/// Normal appearance stream of the annotation (picked by its `AS` state when there are many,
+/// like checkboxes have).
+fn appearance(doc: &Document, annot: &Dictionary) -> Option<ObjectId> {
+    let normal = annot
+        .get(b"AP")
+        .and_then(|ap| doc.dereference(ap))
+        .and_then(|(_, ap)| ap.as_dict())
+        .and_then(|ap| ap.get(b"N"))
+        .ok()?;
+    match normal {
+        Object::Reference(id) if doc.get_object(*id).is_ok_and(|n| n.as_stream().is_ok()) => {
+            Some(*id)
+        }
+        states => {
+            let state = annot.get(b"AS").and_then(Object::as_name).ok()?;
+            doc.dereference(states)
+                .and_then(|(_, s)| s.as_dict())
+                .and_then(|s| s.get(state))
+                .and_then(Object::as_reference)
+                .ok()
+        }
+    }
+}
+
+/// Matrix (`cm` operands) that fits the appearance stream (its `BBox` transformed by its
+/// `Matrix`) into the annotation `Rect`.
+fn placement(doc: &Document, annot: &Dictionary, form: &Dictionary) -> Option<[f32; 6]> {
+    let rect = page::rect(doc, annot.get(b"Rect").ok()?)?;
+    let bbox = page::rect(doc, form.get(b"BBox").ok()?)?;
+    let matrix = form
+        .get(b"Matrix")
+        .and_then(Object::as_array)
+        .ok()
+        .and_then(|m| {
+            m.iter()
+                .map(|v| v.as_float().ok())
+                .collect::<Option<Vec<_>>>()
+        })
+        .and_then(|m| <[f32; 6]>::try_from(m).ok())
+        .unwrap_or([1.0, 0.0, 0.0, 1.0, 0.0, 0.0]);
+    let [x0, y0, x1, y1] = page::transform_rect(matrix, bbox);
+    let scale = |target: f32, source: f32| match source > 0.0 {
+        true => target / source,
+        false => 1.0,
+    };
+    let (sx, sy) = (
+        scale(rect[2] - rect[0], x1 - x0),
+        scale(rect[3] - rect[1], y1 - y0),
+    );
+    Some([sx, 0.0, 0.0, sy, rect[0] - x0 * sx, rect[1] - y0 * sy])
+}
+
+/// Fully qualified name of the form field the widget belongs to (the partial names of its
+/// ancestors joined by dots).
+fn field_name(doc: &Document, widget: &Dictionary) -> Option<String> {
+    let mut names = Vec::new();
+    let mut node = Some(widget);
+    let mut seen = HashSet::new();
+    while let Some(dict) = node {
+        if let Ok(name) = dict.get(b"T").and_then(decode_text_string) {
+            names.push(name);
+        }
+        node = dict
+            .get(b"Parent")
+            .and_then(Object::as_reference)
+            .ok()
+            .filter(|id| seen.insert(*id))
+            .and_then(|id| doc.get_dictionary(id).ok());
+    }
+    names.reverse();
+    (!names.is_empty()).then(|| names.join("."))
+}
+
+/// Draws the normal appearance of every widget and markup annotation into the page content, then
+/// removes them (popups go too) and the `AcroForm`, so the merged form fields can't be edited
+/// anymore (and their names don't collide). Links are kept.
+///
+/// Returns the report of the annotations dropped without being drawn, since they have no
+/// appearance (so their field values are lost).
+pub fn apply(doc: &mut Document, origins: &[PageOrigin]) -> Result<Report, MergeRunError> {
+    let err = |_| MergeRunError::CouldNotEditMergedPages("flatten".into());
+    let mut dropped = Vec::new();
+    for (page_id, origin) in doc.page_iter().collect::<Vec<_>>().into_iter().zip(origins) {
+        let annots = doc
+            .get_dictionary(page_id)
+            .and_then(|p| p.get(b"Annots"))
+            .and_then(|a| doc.dereference(a))
+            .and_then(|(_, a)| a.as_array())
+            .cloned()
+            .unwrap_or_default();
+        if annots.is_empty() {
+            continue;
+        }
+        let mut kept = Vec::new();
+        let mut operations = Vec::new();
+        for object in annots {
+            let Ok(annot) = doc
+                .dereference(&object)
+                .and_then(|(_, a)| a.as_dict())
+                .cloned()
+            else {
+                continue;
+            };
+            let subtype = annot.get(b"Subtype").and_then(Object::as_name).ok();
+            if subtype == Some(b"Link".as_slice()) {
+                kept.push(object);
+                continue;
+            }
+            let flags = annot.get(b"F").and_then(Object::as_i64).unwrap_or(0);
+            if subtype == Some(b"Popup".as_slice()) || flags & HIDDEN != 0 {
+                continue;
+            }
+            let placed = appearance(doc, &annot).and_then(|form_id| {
+                let form = doc.get_object(form_id).and_then(Object::as_stream).ok()?;
+                Some((form_id, placement(doc, &annot, &form.dict)?))
+            });
+            let Some((form_id, matrix)) = placed else {
+                let what = match field_name(doc, &annot) {
+                    Some(name) => format!("`{}` field", name.cyan()),
+                    None => format!(
+                        "{} annotation",
+                        String::from_utf8_lossy(subtype.unwrap_or(b"unknown"))
+                    ),
+                };
+                dropped.push(format!("{origin}: {what}"));
+                continue;
+            };
+            if let Ok(form) = doc.get_object_mut(form_id).and_then(Object::as_stream_mut) {
+                form.dict.set("Type", "XObject");
+                form.dict.set("Subtype", "Form");
+            }
+            let name = format!("{APPEARANCE_NAME}{}", operations.len() / 4);
+            page::add_resource(doc, page_id, b"XObject", name.as_bytes(), form_id.into())
+                .map_err(err)?;
+            operations.extend([
+                Operation::new("q", vec![]),
+                Operation::new("cm", matrix.into_iter().map(Object::Real).collect()),
+                Operation::new("Do", vec![Object::Name(name.into_bytes())]),
+                Operation::new("Q", vec![]),
+            ]);
+        }
+        if !operations.is_empty() {
+            let content = Content { operations }.encode().map_err(err)?;
+            page::stamp_over(doc, page_id, content).map_err(err)?;
+        }
+        let page = doc.get_dictionary_mut(page_id).map_err(err)?;
+        if kept.is_empty() {
+            page.remove(b"Annots");
+        } else {
+            page.set("Annots", kept);
+        }
+    }
+    doc.catalog_mut().map_err(err)?.remove(b"AcroForm");
+    doc.prune_objects();
+    Ok(Report {
+        title: "Annotations dropped by flattening (no appearance)".into(),
+        items: dropped,
+    })
+}
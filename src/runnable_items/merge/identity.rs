@@ -0,0 +1,67 @@
+use sha2::{Digest, Sha256};
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+/// How two inputs are told to be the same file.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum DedupeBy {
+    /// Same (normalized) path.
+    Path,
+    /// Same file on disk, even through symlinks, absolute paths or hardlinks.
+    #[default]
+    Inode,
+    /// Same file on disk or same file content (copies with different names).
+    Hash,
+}
+
+impl FromStr for DedupeBy {
+    type Err = ();
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "path" => Ok(Self::Path),
+            "inode" => Ok(Self::Inode),
+            "hash" => Ok(Self::Hash),
+            _ => Err(()),
+        }
+    }
+}
+
+/// What identifies an input (depends on the [`DedupeBy`] mode).
+#[derive(PartialEq, Eq, Hash)]
+pub enum FileId {
+    Path(PathBuf),
+    Inode(u64, u64),
+    Hash([u8; 32]),
+}
+
+/// Device and inode of the file (its canonical path where inodes aren't available).
+#[cfg(unix)]
+fn inode(path: &Path) -> io::Result<FileId> {
+    use std::os::unix::fs::MetadataExt;
+    let metadata = fs::metadata(path)?;
+    Ok(FileId::Inode(metadata.dev(), metadata.ino()))
+}
+
+/// Device and inode of the file (its canonical path where inodes aren't available).
+#[cfg(not(unix))]
+fn inode(path: &Path) -> io::Result<FileId> {
+    fs::canonicalize(path).map(FileId::Path)
+}
+
+impl DedupeBy {
+    /// Identity of the file at `path` (directories are never hashed, they're compared by inode).
+    pub fn id(&self, path: &Path) -> io::Result<FileId> {
+        match self {
+            Self::Path => Ok(FileId::Path(path.into())),
+            Self::Hash if path.is_file() => {
+                let mut hasher = Sha256::new();
+                io::copy(&mut fs::File::open(path)?, &mut hasher)?;
+                Ok(FileId::Hash(hasher.finalize().into()))
+            }
+            Self::Inode | Self::Hash => inode(path),
+        }
+    }
+}
@@ -0,0 +1,120 @@
+//! # Image XObjects
+//!
+//! Loads PNG/JPEG files as PDF image XObjects (JPEG data is embedded as is, PNG data is decoded
+//! and its alpha channel goes to a soft mask).
+use lopdf::{Document, Object, ObjectId, Stream, dictionary};
+use std::{fs, path::Path};
+
+/// An image XObject added to the document.
+pub struct Image {
+    /// The XObject id.
+    pub id: ObjectId,
+    /// Image width (in pixels).
+    pub width: u32,
+    /// Image height (in pixels).
+    pub height: u32,
+}
+
+/// Adds the image file at `path` to the document. Returns [`None`] if the file can't be read or
+/// isn't a supported PNG/JPEG file.
+pub fn add_image(doc: &mut Document, path: &Path) -> Option<Image> {
+    let data = fs::read(path).ok()?;
+    if data.starts_with(&[0xFF, 0xD8]) {
+        add_jpeg(doc, data)
+    } else {
+        add_png(doc, &data)
+    }
+}
+
+/// Embeds the JPEG data (`DCTDecode` filter) reading the image info from its `SOF` segment.
+fn add_jpeg(doc: &mut Document, data: Vec<u8>) -> Option<Image> {
+    let mut at = 2;
+    let (width, height, components) = loop {
+        if *data.get(at)? != 0xFF {
+            return None;
+        }
+        let marker = *data.get(at + 1)?;
+        let length = u16::from_be_bytes([*data.get(at + 2)?, *data.get(at + 3)?]) as usize;
+        // SOF markers (except DHT, JPG and DAC ones)
+        if (0xC0..=0xCF).contains(&marker) && ![0xC4, 0xC8, 0xCC].contains(&marker) {
+            // precision, height, width and components at least
+            let sof = data.get(at + 4..at + 2 + length).filter(|s| s.len() >= 6)?;
+            break (
+                u16::from_be_bytes([sof[3], sof[4]]) as u32,
+                u16::from_be_bytes([sof[1], sof[2]]) as u32,
+                sof[5],
+            );
+        }
+        at += 2 + length;
+    };
+    let color_space = match components {
+        1 => "DeviceGray",
+        3 => "DeviceRGB",
+        4 => "DeviceCMYK",
+        _ => return None,
+    };
+    let id = doc.add_object(Stream::new(
+        dictionary! {
+            "Type" => "XObject",
+            "Subtype" => "Image",
+            "Width" => width,
+            "Height" => height,
+            "ColorSpace" => color_space,
+            "BitsPerComponent" => 8,
+            "Filter" => "DCTDecode",
+        },
+        data,
+    ));
+    Some(Image { id, width, height })
+}
+
+/// Decodes the PNG data into 8 bits samples, splitting the alpha channel (if any) into a soft
+/// mask.
+fn add_png(doc: &mut Document, data: &[u8]) -> Option<Image> {
+    let mut decoder = png::Decoder::new(data);
+    decoder.set_transformations(png::Transformations::EXPAND | png::Transformations::STRIP_16);
+    let mut reader = decoder.read_info().ok()?;
+    let mut buffer = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut buffer).ok()?;
+    buffer.truncate(info.buffer_size());
+    let (color_space, colors, has_alpha) = match info.color_type {
+        png::ColorType::Grayscale => ("DeviceGray", 1, false),
+        png::ColorType::GrayscaleAlpha => ("DeviceGray", 1, true),
+        png::ColorType::Rgb => ("DeviceRGB", 3, false),
+        png::ColorType::Rgba => ("DeviceRGB", 3, true),
+        // already expanded into RGB by the decoder transformations
+        png::ColorType::Indexed => return None,
+    };
+    let (samples, alpha): (Vec<_>, Vec<_>) = buffer
+        .chunks(colors + has_alpha as usize)
+        .map(|px| (px[..colors].to_vec(), px.get(colors).copied()))
+        .unzip();
+    let mut dict = dictionary! {
+        "Type" => "XObject",
+        "Subtype" => "Image",
+        "Width" => info.width,
+        "Height" => info.height,
+        "ColorSpace" => color_space,
+        "BitsPerComponent" => 8,
+    };
+    if has_alpha {
+        let mask = doc.add_object(Stream::new(
+            dictionary! {
+                "Type" => "XObject",
+                "Subtype" => "Image",
+                "Width" => info.width,
+                "Height" => info.height,
+                "ColorSpace" => "DeviceGray",
+                "BitsPerComponent" => 8,
+            },
+            alpha.into_iter().flatten().collect(),
+        ));
+        dict.set("SMask", Object::Reference(mask));
+    }
+    let id = doc.add_object(Stream::new(dict, samples.concat()));
+    Some(Image {
+        id,
+        width: info.width,
+        height: info.height,
+    })
+}
@@ -0,0 +1,335 @@
+use super::MergeRunError;
+use flate2::read::ZlibDecoder;
+use lopdf::{Document, Object, Stream, content::Content, filters::png};
+use std::{fs::File, io::Read, path::Path};
+
+/// Default nesting depth allowed for arrays and dictionaries (real files rarely go past ten).
+pub const DEFAULT_MAX_NESTING: usize = 64;
+/// Highest nesting depth that can be allowed, since lopdf parses the objects recursively (on
+/// worker threads with small stacks) and overflows the stack within a few hundreds.
+pub const MAX_NESTING: usize = 128;
+/// Bytes an object stream can have once decompressed when `max_stream_bytes` isn't set (real
+/// ones hold a few hundred kilobytes), since lopdf unpacks them whole while loading.
+pub const OBJECT_STREAM_CEILING: u64 = 64 * 1024 * 1024;
+
+/// Resource limits every input must stay within, so a hostile one can't exhaust the memory (or
+/// the stack) while it's loaded. Only the nesting depth is limited by default.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Limits {
+    /// Objects an input can have.
+    pub max_objects: Option<usize>,
+    /// Bytes a stream can have once decompressed (only `FlateDecode` streams are decompressed,
+    /// the other ones are measured as stored).
+    pub max_stream_bytes: Option<u64>,
+    /// Pages an input can have.
+    pub max_pages: Option<usize>,
+    /// Bytes an input file can have.
+    pub max_input_size: Option<u64>,
+    /// Nesting depth of the arrays and dictionaries.
+    pub max_nesting: usize,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self::NONE
+    }
+}
+
+/// The exceeded limit (with its value).
+#[derive(Debug, Clone, PartialEq)]
+pub enum Limit {
+    Objects(usize),
+    StreamBytes(u64),
+    Pages(usize),
+    InputSize(u64),
+    Nesting(usize),
+}
+
+impl Limit {
+    /// Flag setting the limit.
+    pub fn flag(&self) -> &'static str {
+        match self {
+            Self::Objects(_) => "--max-objects",
+            Self::StreamBytes(_) => "--max-stream-bytes",
+            Self::Pages(_) => "--max-pages",
+            Self::InputSize(_) => "--max-input-size",
+            Self::Nesting(_) => "--max-nesting",
+        }
+    }
+
+    /// Value of the limit.
+    pub fn value(&self) -> u64 {
+        match self {
+            Self::Objects(n) | Self::Pages(n) | Self::Nesting(n) => *n as u64,
+            Self::StreamBytes(n) | Self::InputSize(n) => *n,
+        }
+    }
+}
+
+/// Bytes of the stream once decompressed, counting up to `max + 1` at most (so a compression
+/// bomb is never fully inflated).
+fn decoded_len(stream: &Stream, max: u64) -> u64 {
+    let is_flate = stream.filters().is_ok_and(|f| {
+        f.first()
+            .is_some_and(|f| *f == b"FlateDecode" || *f == b"Fl")
+    });
+    match is_flate {
+        true => {
+            let mut decoder = ZlibDecoder::new(stream.content.as_slice()).take(max + 1);
+            let mut len = 0;
+            let mut buf = [0; 8192];
+            // a corrupted stream counts up to where it could be decompressed
+            while let Ok(n @ 1..) = decoder.read(&mut buf) {
+                len += n as u64;
+            }
+            len
+        }
+        false => stream.content.len() as u64,
+    }
+}
+
+/// The object stream decompressed, if it fits within `max` bytes (inflating `max + 1` bytes at
+/// most, so a compression bomb is never fully inflated). Only unfiltered and `FlateDecode`
+/// streams (PNG predictors included) are decompressed this way, the other filters can't be
+/// bounded, so they don't fit.
+fn decoded(stream: &Stream, max: u64) -> Option<Vec<u8>> {
+    let filters = stream.filters().unwrap_or_default();
+    let content = match filters.as_slice() {
+        [] => stream.content.clone(),
+        [b"FlateDecode" | b"Fl"] => {
+            let mut decoder = ZlibDecoder::new(stream.content.as_slice()).take(max + 1);
+            let mut content = Vec::new();
+            let mut buf = [0; 8192];
+            // a corrupted stream is kept up to where it could be decompressed
+            while let Ok(n @ 1..) = decoder.read(&mut buf) {
+                content.extend_from_slice(&buf[..n]);
+            }
+            content
+        }
+        _ => return None,
+    };
+    if content.len() as u64 > max {
+        return None;
+    }
+    let params = stream
+        .dict
+        .get(b"DecodeParms")
+        .and_then(Object::as_dict)
+        .ok();
+    let param = |key: &[u8]| params?.get(key).and_then(Object::as_i64).ok();
+    match param(b"Predictor") {
+        Some(10..) => {
+            let columns = param(b"Columns").unwrap_or(1).max(1) as usize;
+            let colors = param(b"Colors").unwrap_or(1).max(1) as usize;
+            let bits = param(b"BitsPerComponent").unwrap_or(8).max(1) as usize;
+            let bytes_per_pixel = (colors * bits).div_ceil(8);
+            // lopdf can't unpack it either when the predictor fails
+            png::decode_frame(&content, bytes_per_pixel, columns)
+                .ok()
+                .or(Some(content))
+        }
+        _ => Some(content),
+    }
+}
+
+/// What a raw scan of PDF bytes found (strings, comments, names and stream data are skipped).
+struct Scan<'a> {
+    /// Objects defined (`obj` keywords, so the updated objects of incremental saves count again).
+    objects: usize,
+    /// Deepest nesting of arrays and dictionaries (the depth restarts at each `endobj`).
+    nesting: usize,
+    /// Dictionary (up to the `stream` keyword) and data of each object stream.
+    object_streams: Vec<(&'a [u8], &'a [u8])>,
+}
+
+/// Scans the PDF `bytes` without parsing them, so nothing is allocated per object (and a deep
+/// nesting can't overflow the stack).
+fn scan(bytes: &[u8]) -> Scan<'_> {
+    let mut scan = Scan {
+        objects: 0,
+        nesting: 0,
+        object_streams: Vec::new(),
+    };
+    // `head` is where the dictionary of the next stream may start
+    let (mut depth, mut head, mut i) = (0usize, 0, 0);
+    let is_delimiter = |b: u8| b.is_ascii_whitespace() || b"()<>[]{}/%".contains(&b);
+    while i < bytes.len() {
+        match bytes[i] {
+            b'%' => {
+                while i < bytes.len() && !matches!(bytes[i], b'\r' | b'\n') {
+                    i += 1;
+                }
+            }
+            b'(' => {
+                let mut open = 1;
+                while open > 0 && i + 1 < bytes.len() {
+                    i += 1;
+                    match bytes[i] {
+                        b'\\' => i += 1,
+                        b'(' => open += 1,
+                        b')' => open -= 1,
+                        _ => {}
+                    }
+                }
+            }
+            b'/' => {
+                while i + 1 < bytes.len() && !is_delimiter(bytes[i + 1]) {
+                    i += 1;
+                }
+            }
+            b'<' if bytes.get(i + 1) == Some(&b'<') => {
+                if depth == 0 {
+                    head = i;
+                }
+                depth += 1;
+                i += 1;
+            }
+            b'<' => {
+                while i < bytes.len() && bytes[i] != b'>' {
+                    i += 1;
+                }
+            }
+            b'>' if bytes.get(i + 1) == Some(&b'>') => {
+                depth = depth.saturating_sub(1);
+                i += 1;
+            }
+            b'[' => depth += 1,
+            b']' => depth = depth.saturating_sub(1),
+            b'o' | b's' | b'e' if i == 0 || is_delimiter(bytes[i - 1]) => {
+                let rest = &bytes[i..];
+                let is = |word: &[u8]| {
+                    rest.starts_with(word) && rest.get(word.len()).is_none_or(|b| is_delimiter(*b))
+                };
+                if is(b"obj") {
+                    scan.objects += 1;
+                    head = i;
+                } else if is(b"stream") {
+                    let end = rest
+                        .windows(9)
+                        .position(|w| w == b"endstream")
+                        .unwrap_or(rest.len());
+                    if bytes[head..i].windows(7).any(|w| w == b"/ObjStm") {
+                        let data = &rest[6..end];
+                        let data = data
+                            .strip_prefix(b"\r\n")
+                            .or_else(|| data.strip_prefix(b"\n"))
+                            .unwrap_or(data);
+                        scan.object_streams.push((&bytes[head..i + 6], data));
+                    }
+                    // jump over the stream data
+                    i += (end + 8).min(rest.len());
+                    head = i;
+                } else if is(b"endobj") {
+                    depth = 0;
+                }
+            }
+            _ => {}
+        }
+        scan.nesting = scan.nesting.max(depth);
+        i += 1;
+    }
+    scan
+}
+
+/// The object stream made of its raw `dict` (up to the `stream` keyword) and `data`, if it is
+/// one.
+fn object_stream(dict: &[u8], data: &[u8]) -> Option<Stream> {
+    let operation = Content::decode(dict).ok()?.operations.pop()?;
+    let dict = match operation.operands.as_slice() {
+        [Object::Dictionary(dict)] if operation.operator == "stream" => dict.clone(),
+        _ => return None,
+    };
+    dict.has_type(b"ObjStm")
+        .then(|| Stream::new(dict, data.to_vec()))
+}
+
+impl Limits {
+    /// No limit at all but the default nesting depth.
+    pub const NONE: Self = Self {
+        max_objects: None,
+        max_stream_bytes: None,
+        max_pages: None,
+        max_input_size: None,
+        max_nesting: DEFAULT_MAX_NESTING,
+    };
+
+    /// Reads the input at `path`, checking its size, then its nesting, its object count and its
+    /// object streams before lopdf parses it (so a hostile input is rejected before lopdf builds
+    /// anything).
+    pub fn read(&self, path: &Path) -> Result<Vec<u8>, MergeRunError> {
+        let err = || MergeRunError::CouldNotLoadInput(path.into());
+        let exceeded = |limit| MergeRunError::InputExceedsLimit(path.into(), limit);
+        let file = File::open(path).map_err(|_| err())?;
+        let too_large = |len| self.max_input_size.filter(|max| len > *max);
+        if let Some(max) = too_large(file.metadata().map_err(|_| err())?.len()) {
+            return Err(exceeded(Limit::InputSize(max)));
+        }
+        // the file may grow while it's read
+        let mut bytes = Vec::new();
+        file.take(self.max_input_size.map_or(u64::MAX, |max| max + 1))
+            .read_to_end(&mut bytes)
+            .map_err(|_| err())?;
+        if let Some(max) = too_large(bytes.len() as u64) {
+            return Err(exceeded(Limit::InputSize(max)));
+        }
+        self.check_bytes(&bytes).map_err(exceeded)?;
+        Ok(bytes)
+    }
+
+    /// Checks the raw `bytes` of an input. The object streams are checked too since lopdf
+    /// unpacks them while loading (up to [`OBJECT_STREAM_CEILING`] bytes when no stream size is
+    /// set), but the encrypted ones can only be decompressed once decrypted, so only the input
+    /// size bounds them.
+    fn check_bytes(&self, bytes: &[u8]) -> Result<(), Limit> {
+        let found = scan(bytes);
+        if found.nesting > self.max_nesting {
+            return Err(Limit::Nesting(self.max_nesting));
+        }
+        let object_streams: Vec<_> = found
+            .object_streams
+            .into_iter()
+            .filter_map(|(dict, data)| object_stream(dict, data))
+            .collect();
+        let objects = object_streams
+            .iter()
+            .fold(found.objects, |objects, stream| {
+                let held = stream.dict.get(b"N").and_then(Object::as_i64).unwrap_or(0);
+                objects.saturating_add(held.max(0) as usize)
+            });
+        if let Some(max) = self.max_objects
+            && objects > max
+        {
+            return Err(Limit::Objects(max));
+        }
+        let max = self.max_stream_bytes.unwrap_or(OBJECT_STREAM_CEILING);
+        let encrypted = bytes.windows(8).any(|w| w == b"/Encrypt");
+        for stream in object_streams.iter().filter(|_| !encrypted) {
+            let content = decoded(stream, max).ok_or(Limit::StreamBytes(max))?;
+            if scan(&content).nesting > self.max_nesting {
+                return Err(Limit::Nesting(self.max_nesting));
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks the page count and the stream sizes of the loaded input at `path` (the pages can't
+    /// outnumber the objects, so `max_objects` is what bounds the load itself).
+    pub fn check(&self, doc: &Document, path: &Path) -> Result<(), MergeRunError> {
+        let exceeded = |limit| Err(MergeRunError::InputExceedsLimit(path.into(), limit));
+        if let Some(max) = self.max_pages
+            && doc.page_iter().nth(max).is_some()
+        {
+            return exceeded(Limit::Pages(max));
+        }
+        if let Some(max) = self.max_stream_bytes
+            && doc
+                .objects
+                .values()
+                .filter_map(|o| o.as_stream().ok())
+                .any(|s| decoded_len(s, max) > max)
+        {
+            return exceeded(Limit::StreamBytes(max));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,125 @@
+use super::{
+    MergeRunError,
+    page::{self, Geometry},
+};
+use lopdf::{
+    Document, Object,
+    content::{Content, Operation},
+    dictionary,
+};
+
+/// Space around the bleed area where the marks are drawn (slug).
+const SLUG: f32 = 36.0;
+/// Space between the bleed area and the marks.
+const MARK_GAP: f32 = 3.0;
+/// Registration mark radius.
+const TARGET_RADIUS: f32 = 6.0;
+/// Registration color space resource name (`All` separation).
+const COLOR_NAME: &[u8] = b"PdmersRegistration";
+/// Bezier control point distance used to draw circles (for a unit radius).
+const KAPPA: f32 = 0.552_284_8;
+
+/// Printer marks options (bleed, crop marks and registration marks).
+#[derive(Debug, PartialEq)]
+pub struct PrintMarks {
+    /// How much the pages are enlarged beyond the trim area.
+    pub bleed: f32,
+}
+
+/// Operations that draw a registration mark (a circle with a cross) centered at (`x`, `y`).
+fn registration_mark(x: f32, y: f32) -> Vec<Operation> {
+    let (r, k) = (TARGET_RADIUS, TARGET_RADIUS * KAPPA);
+    let cross = r * 1.5;
+    let curve =
+        |points: [f32; 6]| Operation::new("c", points.into_iter().map(Object::Real).collect());
+    vec![
+        Operation::new("m", vec![(x + r).into(), y.into()]),
+        curve([x + r, y + k, x + k, y + r, x, y + r]),
+        curve([x - k, y + r, x - r, y + k, x - r, y]),
+        curve([x - r, y - k, x - k, y - r, x, y - r]),
+        curve([x + k, y - r, x + r, y - k, x + r, y]),
+        Operation::new("m", vec![(x - cross).into(), y.into()]),
+        Operation::new("l", vec![(x + cross).into(), y.into()]),
+        Operation::new("m", vec![x.into(), (y - cross).into()]),
+        Operation::new("l", vec![x.into(), (y + cross).into()]),
+    ]
+}
+
+impl PrintMarks {
+    /// Content that draws the marks around the `trim` area of a page of the given size.
+    fn content(&self, trim: [f32; 4], (width, height): (f32, f32)) -> lopdf::Result<Vec<u8>> {
+        let [x0, y0, x1, y1] = trim;
+        let (mid_x, mid_y) = ((x0 + x1) / 2.0, (y0 + y1) / 2.0);
+        let mut operations = vec![
+            Operation::new("q", vec![]),
+            Operation::new("CS", vec![Object::Name(COLOR_NAME.into())]),
+            Operation::new("SC", vec![1.into()]),
+        ];
+        operations.extend(page::crop_marks(
+            trim,
+            self.bleed + MARK_GAP,
+            SLUG - 2.0 * MARK_GAP,
+        ));
+        operations.push(Operation::new("w", vec![0.25.into()]));
+        for (x, y) in [
+            (mid_x, SLUG / 2.0),
+            (mid_x, height - SLUG / 2.0),
+            (SLUG / 2.0, mid_y),
+            (width - SLUG / 2.0, mid_y),
+        ] {
+            operations.extend(registration_mark(x, y));
+        }
+        operations.extend([Operation::new("S", vec![]), Operation::new("Q", vec![])]);
+        Content { operations }.encode()
+    }
+
+    /// Enlarges every page by the bleed (and the marks space), setting its `TrimBox` (the old
+    /// visible box) and `BleedBox`, then draws the crop and registration marks outside of them.
+    ///
+    /// The bleed shows the page content beyond its visible box (if any).
+    pub fn apply(&self, doc: &mut Document) -> Result<(), MergeRunError> {
+        let err = |_| MergeRunError::CouldNotEditMergedPages("print marks".into());
+        let registration = doc.add_object(vec![
+            Object::Name(b"Separation".to_vec()),
+            Object::Name(b"All".to_vec()),
+            Object::Name(b"DeviceCMYK".to_vec()),
+            dictionary! {
+                "FunctionType" => 2,
+                "Domain" => vec![0.into(), 1.into()],
+                "C0" => vec![0.into(), 0.into(), 0.into(), 0.into()],
+                "C1" => vec![1.into(), 1.into(), 1.into(), 1.into()],
+                "N" => 1,
+            }
+            .into(),
+        ]);
+        let (b, s) = (self.bleed, SLUG);
+        for page_id in doc.page_iter().collect::<Vec<_>>() {
+            let geometry = Geometry::of(doc, page_id);
+            let (w, h) = geometry.visual_size();
+            let [x0, y0, x1, y1] = geometry.bbox;
+            let grown = [x0 - b, y0 - b, x1 + b, y1 + b];
+            let bleed = Geometry {
+                bbox: grown,
+                ..geometry
+            };
+            let (width, height) = (w + 2.0 * (b + s), h + 2.0 * (b + s));
+            doc.get_dictionary_mut(page_id)
+                .map_err(err)?
+                .set("CropBox", grown.map(Object::Real).to_vec());
+            let matrix = page::multiply(bleed.user_matrix(), [1.0, 0.0, 0.0, 1.0, s, s]);
+            page::transform(doc, page_id, matrix, [0.0, 0.0, width, height]).map_err(err)?;
+            let trim = [b + s, b + s, b + s + w, b + s + h];
+            let page = doc.get_dictionary_mut(page_id).map_err(err)?;
+            page.set("TrimBox", trim.map(Object::Real).to_vec());
+            page.set(
+                "BleedBox",
+                [s, s, width - s, height - s].map(Object::Real).to_vec(),
+            );
+            page::add_resource(doc, page_id, b"ColorSpace", COLOR_NAME, registration.into())
+                .map_err(err)?;
+            let content = self.content(trim, (width, height)).map_err(err)?;
+            page::stamp_over(doc, page_id, content).map_err(err)?;
+        }
+        Ok(())
+    }
+}
@@ -2,9 +2,37 @@
 //!
 //! This module provides basic data types to convert the [`crate::cli::subcommands::MergeArgs`]
 //! into a runnable executor.
+mod anonymize;
+mod bates;
+mod blank;
+mod booklet;
+mod crop;
+mod dedupe;
 mod depth;
+mod encrypt;
 mod errors;
+mod flatten;
+mod identity;
+mod image;
+mod limits;
+mod marks;
+mod nup;
+mod order;
+mod orient;
+mod page;
+mod page_size;
+mod paper;
+mod password;
+mod provenance;
+mod root;
 mod run_success;
+mod sanitize;
+mod sign;
+mod signed;
+mod stamp;
+mod symlinks;
+mod tile;
+mod watermark;
 
 #[cfg(test)]
 mod tests;
@@ -18,17 +46,43 @@ use crate::{
         run::RunnableItem,
     },
 };
+use bates::Bates;
+use blank::Blank;
+use booklet::Booklet;
+use crop::Crop;
+use dedupe::PageHasher;
 use depth::Depth;
+use encrypt::Encryption;
 pub use errors::*;
+use identity::DedupeBy;
+use limits::Limits;
 use lopdf::{self, Bookmark, Document, Object, ObjectId};
-pub use run_success::RunSuccess;
+use marks::PrintMarks;
+use nup::{Grid, NUp};
+use order::PageOrder;
+use orient::Orientation;
+use page::{Anchor, PageOrigin};
+use page_size::PageSize;
+use paper::PaperSize;
+use password::Passwords;
+use provenance::Generated;
+use root::Root;
+pub use run_success::{Report, RunSuccess};
+use sign::Signing;
+use signed::SignedBase;
+use stamp::Layer;
 use std::{
-    collections::{BTreeMap, HashSet},
+    collections::{BTreeMap, HashMap, hash_map::Entry},
     ffi::OsStr,
     fs,
+    ops::RangeBounds,
     path::{Path, PathBuf},
+    str::FromStr,
     time::Instant,
 };
+use symlinks::{FollowSymlinks, Links};
+use tile::Tile;
+use watermark::Watermark;
 
 /// Merge action executor. It stores data to be converted in input/output file paths and action
 /// arguments.
@@ -42,10 +96,94 @@ pub struct Merge {
     ovrrd: bool,
     /// Allow input repetition.
     repetition: bool,
+    /// How repeated inputs are detected.
+    dedupe_by: DedupeBy,
     /// Catch files until the until the defined depth.
     depth: Depth,
     /// Create parent dirs of the output if not exists.
     parent: bool,
+    /// Stamp Bates numbers on the merged pages.
+    bates: Option<Bates>,
+    /// Draw a text/image watermark on the merged pages.
+    watermark: Option<Watermark>,
+    /// PDF whose pages are drawn under the merged pages.
+    underlay: Option<PathBuf>,
+    /// PDF whose pages are drawn over the merged pages.
+    overlay: Option<PathBuf>,
+    /// N-up layout options.
+    nup: Option<NUp>,
+    /// Booklet imposition options.
+    booklet: Option<Booklet>,
+    /// Poster tiling options.
+    tile: Option<Tile>,
+    /// Page size normalization options.
+    page_size: Option<PageSize>,
+    /// Orientation all pages should have.
+    orient: Option<Orientation>,
+    /// How the pages are cropped.
+    crop: Option<Crop>,
+    /// Binding margin added to the pages.
+    margin: Option<f32>,
+    /// Printer marks options.
+    print_marks: Option<PrintMarks>,
+    /// Blank pages detection options.
+    blank: Option<Blank>,
+    /// Drop the pages identical to a previous one.
+    dedupe_pages: bool,
+    /// How the final page sequence is built.
+    order: Option<PageOrder>,
+    /// Draw the annotations into the page content, removing the form fields.
+    flatten: bool,
+    /// Where the passwords of the encrypted inputs come from.
+    passwords: Passwords,
+    /// Output encryption options.
+    encryption: Option<Encryption>,
+    /// Strip the active content (scripts, actions, embedded files, ...) of the inputs.
+    sanitize: bool,
+    /// Strip the metadata identifying the original authors or their tools.
+    anonymize: bool,
+    /// Fail when an input is digitally signed.
+    refuse_signed: bool,
+    /// Input whose signature is kept (the other ones are appended as an incremental update).
+    keep_signature_of: Option<PathBuf>,
+    /// Output signing options.
+    signing: Option<Signing>,
+    /// Resource limits every input must stay within.
+    limits: Limits,
+    /// Sandbox directory every input and output must resolve inside.
+    root: Option<PathBuf>,
+    /// Which symlinks found within the input directories are followed.
+    follow_symlinks: FollowSymlinks,
+    /// Merge the files generated by earlier runs found within the input directories.
+    keep_generated: bool,
+}
+
+/// Parses an optional flag value, returning the `default` one when it's not provided.
+fn parse_or<T: FromStr>(
+    value: Option<String>,
+    default: T,
+    err: impl FnOnce(String) -> MergeBuildError,
+) -> Result<T, MergeBuildError> {
+    match value {
+        Some(v) => v.trim().parse().map_err(|_| err(v)),
+        None => Ok(default),
+    }
+}
+
+/// Same as [`parse_or`], but the parsed value must be within the given `range`.
+fn parse_within<T: FromStr + PartialOrd>(
+    value: Option<String>,
+    default: T,
+    range: impl RangeBounds<T>,
+    err: impl FnOnce(String) -> MergeBuildError,
+) -> Result<T, MergeBuildError> {
+    match value {
+        Some(v) => match v.trim().parse().ok().filter(|p| range.contains(p)) {
+            Some(p) => Ok(p),
+            None => Err(err(v)),
+        },
+        None => Ok(default),
+    }
 }
 
 impl TryFrom<MergeArgs> for Merge {
@@ -56,32 +194,442 @@ impl TryFrom<MergeArgs> for Merge {
             output,
             override_output,
             allow_repetition,
+            dedupe_by,
             depth,
             parent,
+            bates,
+            bates_suffix,
+            bates_start,
+            bates_digits,
+            bates_position,
+            bates_log,
+            watermark_text,
+            watermark_image,
+            watermark_size,
+            watermark_rotation,
+            watermark_color,
+            watermark_opacity,
+            watermark_pages,
+            watermark_under,
+            underlay,
+            overlay,
+            nup,
+            nup_paper,
+            nup_gutter,
+            nup_border,
+            booklet,
+            booklet_paper,
+            booklet_gutter,
+            booklet_creep,
+            booklet_signature,
+            tile,
+            tile_overlap,
+            tile_margin,
+            page_size,
+            page_scale,
+            orient,
+            crop,
+            add_margin,
+            print_marks,
+            bleed,
+            drop_blank,
+            blank_sensitivity,
+            dedupe_pages,
+            reverse,
+            order,
+            flatten,
+            password_file,
+            password_prompt,
+            encrypt,
+            encrypt_algorithm,
+            user_password,
+            owner_password,
+            deny,
+            sanitize,
+            anonymize,
+            refuse_signed,
+            keep_signature_of,
+            sign,
+            sign_password_env,
+            sign_reason,
+            sign_location,
+            sign_visible,
+            sign_page,
+            max_objects,
+            max_stream_bytes,
+            max_pages,
+            max_input_size,
+            max_nesting,
+            root,
+            follow_symlinks,
+            keep_generated,
         } = value;
+        let mut password_env = HashMap::new();
         let input = match input {
             x if x.is_empty() => Err(MergeBuildError::InputIsEmpty),
-            x => Ok(x
+            x => x
                 .into_iter()
-                .map(|path| normalize_path_buf(Path::new(&path)))
-                .collect()),
+                .map(|arg| {
+                    let Some((path, var)) = arg.rsplit_once(password::ENV_SUFFIX) else {
+                        return Ok(normalize_path_buf(Path::new(&arg)));
+                    };
+                    if var.trim().is_empty() {
+                        return Err(MergeBuildError::UnparseablePasswordEnv(arg));
+                    }
+                    let path = normalize_path_buf(Path::new(path));
+                    password_env.insert(path.clone(), var.trim().to_string());
+                    Ok(path)
+                })
+                .collect(),
         }?;
+        let passwords = Passwords {
+            env: password_env,
+            file: password_file.map(PathBuf::from),
+            prompt: password_prompt,
+        };
         let output = match output {
             Some(o) => Ok(PathBuf::from(o)),
             None => Err(MergeBuildError::OutputIsEmpty),
         }?;
         let ovrrd = override_output;
         let repetition = allow_repetition;
+        let dedupe_by = parse_or(
+            dedupe_by,
+            DedupeBy::default(),
+            MergeBuildError::UnparseableDedupeMode,
+        )?;
+        let follow_symlinks = parse_or(
+            follow_symlinks,
+            FollowSymlinks::default(),
+            MergeBuildError::UnparseableSymlinkPolicy,
+        )?;
         let depth = depth.map_or(Ok(Depth::default()), |d| {
             Depth::try_from(d.trim().to_string())
         })?;
+        let bates_err = |flag: &str| {
+            let flag = flag.to_string();
+            move |v| MergeBuildError::UnparseableBatesOption(flag, v)
+        };
+        let printable = |flag, text: String| match bates::is_printable(&text) {
+            true => Ok(text),
+            false => Err(bates_err(flag)(text)),
+        };
+        let bates = match bates {
+            Some(prefix) => Some(Bates {
+                prefix: printable("--bates", prefix)?,
+                suffix: printable("--bates-suffix", bates_suffix.unwrap_or_default())?,
+                start: parse_or(bates_start, 1, bates_err("--bates-start"))?,
+                digits: parse_or(bates_digits, 6, bates_err("--bates-digits"))?,
+                anchor: parse_or(
+                    bates_position,
+                    Anchor::default(),
+                    bates_err("--bates-position"),
+                )?,
+                log: bates_log.map(PathBuf::from),
+            }),
+            None => None,
+        };
+        let watermark_err = |flag: &str| {
+            let flag = flag.to_string();
+            move |v| MergeBuildError::UnparseableWatermarkOption(flag, v)
+        };
+        let watermark = match (watermark_text, watermark_image) {
+            (None, None) => None,
+            (text, image) => Some(Watermark {
+                text,
+                image: image.map(PathBuf::from),
+                size: parse_within(
+                    watermark_size,
+                    48.0,
+                    f32::MIN_POSITIVE..,
+                    watermark_err("--watermark-size"),
+                )?,
+                rotation: parse_or(
+                    watermark_rotation,
+                    45.0,
+                    watermark_err("--watermark-rotation"),
+                )?,
+                color: parse_or(
+                    watermark_color,
+                    Default::default(),
+                    watermark_err("--watermark-color"),
+                )?,
+                opacity: parse_within(
+                    watermark_opacity,
+                    0.3,
+                    0.0..=1.0,
+                    watermark_err("--watermark-opacity"),
+                )?,
+                pages: parse_or(
+                    watermark_pages,
+                    Default::default(),
+                    watermark_err("--watermark-pages"),
+                )?,
+                under: watermark_under,
+            }),
+        };
+        let nup_err = |flag: &str| {
+            let flag = flag.to_string();
+            move |v| MergeBuildError::UnparseableNUpOption(flag, v)
+        };
+        let nup = match nup {
+            Some(grid) => {
+                let grid: Grid = grid.parse().map_err(|_| nup_err("--nup")(grid.clone()))?;
+                let paper: PaperSize =
+                    parse_or(nup_paper, Default::default(), nup_err("--nup-paper"))?;
+                // the gutters must leave room for the cells, whichever way the sheet is turned
+                // (a single row/column has no gutter, so its room is infinite)
+                let (short, long) = paper.oriented(false);
+                let room = |side: f32, cells: u32| side / (cells - 1) as f32;
+                let gutter = parse_within(
+                    nup_gutter,
+                    0.0,
+                    0.0..room(short, grid.cols).min(room(long, grid.rows)),
+                    nup_err("--nup-gutter"),
+                )?;
+                Some(NUp {
+                    grid,
+                    paper,
+                    gutter,
+                    border: nup_border,
+                })
+            }
+            None => None,
+        };
+        let booklet_err = |flag: &str| {
+            let flag = flag.to_string();
+            move |v| MergeBuildError::UnparseableBookletOption(flag, v)
+        };
+        let booklet = match booklet {
+            true => {
+                let paper: PaperSize = parse_or(
+                    booklet_paper,
+                    Default::default(),
+                    booklet_err("--booklet-paper"),
+                )?;
+                // the gutter must leave room for the two half-sheets of the (landscape) sides
+                let side = paper.oriented(true).0;
+                let gutter = parse_within(
+                    booklet_gutter,
+                    0.0,
+                    0.0..side,
+                    booklet_err("--booklet-gutter"),
+                )?;
+                let signature = booklet_signature
+                    .map(|n| parse_within(Some(n), 1, 1.., booklet_err("--booklet-signature")))
+                    .transpose()?;
+                // the innermost sheet of the signatures can't be shifted past the fold (checked
+                // once the pages are counted when there's a single signature)
+                let max_creep = booklet::max_creep(gutter, signature.unwrap_or(1) as usize);
+                Some(Booklet {
+                    paper,
+                    gutter,
+                    creep: parse_within(
+                        booklet_creep,
+                        0.0,
+                        0.0..=max_creep,
+                        booklet_err("--booklet-creep"),
+                    )?,
+                    signature,
+                })
+            }
+            false => None,
+        };
+        let tile_err = |flag: &str| {
+            let flag = flag.to_string();
+            move |v| MergeBuildError::UnparseableTileOption(flag, v)
+        };
+        let tile = match tile {
+            Some(paper) => {
+                let paper: PaperSize =
+                    parse_or(Some(paper), Default::default(), tile_err("--tile"))?;
+                let side = paper.oriented(false).0;
+                let margin = parse_within(
+                    tile_margin,
+                    36.0,
+                    0.0..side / 2.0,
+                    tile_err("--tile-margin"),
+                )?;
+                let overlap = parse_within(
+                    tile_overlap,
+                    18.0f32.min(side / 2.0 - margin),
+                    0.0..side - 2.0 * margin,
+                    tile_err("--tile-overlap"),
+                )?;
+                Some(Tile {
+                    paper,
+                    overlap,
+                    margin,
+                })
+            }
+            None => None,
+        };
+        let page_size_err = |flag: &str| {
+            let flag = flag.to_string();
+            move |v| MergeBuildError::UnparseablePageSizeOption(flag, v)
+        };
+        let page_size = match page_size {
+            Some(paper) => Some(PageSize {
+                paper: parse_or(
+                    Some(paper),
+                    Default::default(),
+                    page_size_err("--page-size"),
+                )?,
+                scaling: parse_or(
+                    page_scale,
+                    Default::default(),
+                    page_size_err("--page-scale"),
+                )?,
+            }),
+            None => None,
+        };
+        let orient = orient
+            .map(|o| {
+                o.parse()
+                    .map_err(|_| MergeBuildError::UnparseableOrientation(o))
+            })
+            .transpose()?;
+        let page_box_err = |flag: &str| {
+            let flag = flag.to_string();
+            move |v| MergeBuildError::UnparseablePageBoxOption(flag, v)
+        };
+        let crop = crop
+            .map(|c| c.trim().parse().map_err(|_| page_box_err("--crop")(c)))
+            .transpose()?;
+        let margin = add_margin
+            .map(|m| parse_within(Some(m), 0.0, 0.0.., page_box_err("--add-margin")))
+            .transpose()?;
+        let print_marks = match print_marks {
+            true => Some(PrintMarks {
+                bleed: parse_within(bleed, 9.0, 0.0.., MergeBuildError::UnparseableBleed)?,
+            }),
+            false => None,
+        };
+        let blank = match drop_blank {
+            true => Some(Blank {
+                sensitivity: parse_within(
+                    blank_sensitivity,
+                    0.0,
+                    0.0..=1.0,
+                    MergeBuildError::UnparseableBlankSensitivity,
+                )?,
+            }),
+            false => None,
+        };
+        let order = match (order, reverse) {
+            (None, false) => None,
+            (file, reverse) => Some(PageOrder {
+                file: file.map(PathBuf::from),
+                reverse,
+            }),
+        };
+        let encrypt_err = |flag: &str| {
+            let flag = flag.to_string();
+            move |v| MergeBuildError::UnparseableEncryptOption(flag, v)
+        };
+        let encryption = match encrypt {
+            true => Some(Encryption {
+                algorithm: parse_or(
+                    encrypt_algorithm,
+                    Default::default(),
+                    encrypt_err("--encrypt-algorithm"),
+                )?,
+                user: user_password
+                    .map(|p| p.parse().map_err(|_| encrypt_err("--user-password")(p)))
+                    .transpose()?,
+                owner: owner_password
+                    .map(|p| p.parse().map_err(|_| encrypt_err("--owner-password")(p)))
+                    .transpose()?,
+                denied: deny
+                    .map(|d| encrypt::denied(&d).map_err(|_| encrypt_err("--deny")(d)))
+                    .transpose()?
+                    .unwrap_or_default(),
+            }),
+            false => None,
+        };
+        let sign_err = |flag: &str| {
+            let flag = flag.to_string();
+            move |v| MergeBuildError::UnparseableSignOption(flag, v)
+        };
+        let signing = match sign {
+            Some(keystore) => Some(Signing {
+                keystore: PathBuf::from(keystore),
+                password_env: sign_password_env
+                    .map(|v| match v.trim().is_empty() {
+                        true => Err(sign_err("--sign-password-env")(v)),
+                        false => Ok(v.trim().to_string()),
+                    })
+                    .transpose()?,
+                reason: sign_reason,
+                location: sign_location,
+                visible: sign_visible
+                    .map(|a| parse_or(Some(a), Anchor::default(), sign_err("--sign-visible")))
+                    .transpose()?,
+                page: sign_page
+                    .map(|n| parse_within(Some(n), 1, 1.., sign_err("--sign-page")))
+                    .transpose()?,
+            }),
+            None => None,
+        };
+        let limit_err = |flag: &str| {
+            let flag = flag.to_string();
+            move |v| MergeBuildError::UnparseableLimitOption(flag, v)
+        };
+        let limits = Limits {
+            max_objects: max_objects
+                .map(|n| parse_within(Some(n), 1, 1.., limit_err("--max-objects")))
+                .transpose()?,
+            max_stream_bytes: max_stream_bytes
+                .map(|n| parse_within(Some(n), 1, 1.., limit_err("--max-stream-bytes")))
+                .transpose()?,
+            max_pages: max_pages
+                .map(|n| parse_within(Some(n), 1, 1.., limit_err("--max-pages")))
+                .transpose()?,
+            max_input_size: max_input_size
+                .map(|n| parse_within(Some(n), 1, 1.., limit_err("--max-input-size")))
+                .transpose()?,
+            max_nesting: parse_within(
+                max_nesting,
+                limits::DEFAULT_MAX_NESTING,
+                1..=limits::MAX_NESTING,
+                limit_err("--max-nesting"),
+            )?,
+        };
         Ok(Merge {
             input,
             output,
             ovrrd,
             repetition,
+            dedupe_by,
             depth,
             parent,
+            bates,
+            watermark,
+            underlay: underlay.map(PathBuf::from),
+            overlay: overlay.map(PathBuf::from),
+            nup,
+            booklet,
+            tile,
+            page_size,
+            orient,
+            crop,
+            margin,
+            print_marks,
+            blank,
+            dedupe_pages,
+            order,
+            flatten,
+            passwords,
+            encryption,
+            sanitize,
+            anonymize,
+            refuse_signed,
+            keep_signature_of: keep_signature_of.map(|p| normalize_path_buf(Path::new(&p))),
+            signing,
+            limits,
+            root: root.map(PathBuf::from),
+            follow_symlinks,
+            keep_generated,
         })
     }
 }
@@ -89,11 +637,34 @@ impl TryFrom<MergeArgs> for Merge {
 impl CheckableItem for Merge {
     type CheckableOutput = Result<(), MergeCheckError>;
     fn check_item(&self) -> Self::CheckableOutput {
+        // check the sandbox root (directory references are allowed within it)
+        if let Some(dir) = &self.root {
+            let root = Root::open(dir).ok_or(MergeCheckError::RootIsNotDirectory(dir.into()))?;
+            let files = [
+                self.underlay.as_ref(),
+                self.overlay.as_ref(),
+                self.keep_signature_of.as_ref(),
+                self.order.as_ref().and_then(|o| o.file.as_ref()),
+                self.watermark.as_ref().and_then(|w| w.image.as_ref()),
+                self.bates.as_ref().and_then(|b| b.log.as_ref()),
+                Some(&self.output),
+            ];
+            if let Some(path) = self
+                .input
+                .iter()
+                .chain(files.into_iter().flatten())
+                .find(|p| !root.contains(p))
+            {
+                return Err(MergeCheckError::PathIsOutsideRoot(path.into()));
+            }
+        }
+        let is_reference =
+            |path: &Path| self.root.is_none() && path.iter().any(|sd| sd == OsStr::new(".."));
         // check input paths
         let mut inp_count = 0;
         let mut contains_dir = false;
         for path in &self.input {
-            if path.iter().any(|sd| sd == OsStr::new("..")) {
+            if is_reference(path) {
                 return Err(MergeCheckError::InputIsDirectoryReference(path.into()));
             }
             if path.is_file()
@@ -105,7 +676,7 @@ impl CheckableItem for Merge {
                 return Err(MergeCheckError::InputIsNotPdfFile(path.into()));
             }
             match (path, &self.depth) {
-                (p, _) if p.iter().any(|sd| sd == OsStr::new("..")) => {
+                (p, _) if is_reference(p) => {
                     Err(MergeCheckError::InputIsDirectoryReference(path.into()))
                 }
                 (p, _) if p.is_file() && p.extension().is_some_and(|ext| ext != "pdf") => {
@@ -126,18 +697,22 @@ impl CheckableItem for Merge {
             return Err(MergeCheckError::OutputIsDirectory(out));
         } else if out.extension().is_some_and(|ext| ext != "pdf") {
             return Err(MergeCheckError::OutputIsNotPdfFile(out));
-        } else if out.iter().any(|sd| sd == OsStr::new("..")) {
+        } else if is_reference(&out) {
             return Err(MergeCheckError::OutputIsDirectoryReference(out));
         }
-        // check repetitions
+        // check repetitions (inputs that can't be read are left to the run step)
         if !self.repetition {
-            let mut remains = self.input.clone();
-            let mut pop = remains.pop();
-            while let Some(p) = pop {
-                if remains.contains(&p) {
-                    return Err(MergeCheckError::InputRepetitionWithoutFlag(p));
+            let mut seen = HashMap::new();
+            for path in &self.input {
+                let Ok(id) = self.dedupe_by.id(path) else {
+                    continue;
+                };
+                if let Some(first) = seen.insert(id, path) {
+                    return Err(MergeCheckError::InputRepetitionWithoutFlag(
+                        first.into(),
+                        path.into(),
+                    ));
                 }
-                pop = remains.pop();
             }
         }
         // check parent flag
@@ -148,6 +723,69 @@ impl CheckableItem for Merge {
                 return Err(MergeCheckError::ParentOutputWithoutFlag(out));
             }
         }
+        // check bates log path
+        if let Some(log) = self.bates.as_ref().and_then(|b| b.log.clone()) {
+            if log.is_dir() {
+                return Err(MergeCheckError::OutputIsDirectory(log));
+            } else if log.extension().is_none_or(|ext| ext != "csv") {
+                return Err(MergeCheckError::BatesLogIsNotCsvFile(log));
+            } else if log.exists() && !self.ovrrd {
+                return Err(MergeCheckError::OutputAlreadyExists(log));
+            }
+        }
+        // check underlay/overlay paths
+        for stamp in [&self.underlay, &self.overlay].into_iter().flatten() {
+            if !stamp.is_file() || stamp.extension().is_none_or(|ext| ext != "pdf") {
+                return Err(MergeCheckError::StampIsNotPdfFile(stamp.into()));
+            }
+        }
+        // check password file path
+        if let Some(file) = self.passwords.file.clone()
+            && !file.is_file()
+        {
+            return Err(MergeCheckError::PasswordFileIsNotFile(file));
+        }
+        // check encryption password files
+        for secret in self
+            .encryption
+            .iter()
+            .flat_map(|e| [&e.user, &e.owner])
+            .flatten()
+        {
+            if let encrypt::Secret::File(file) = secret
+                && !file.is_file()
+            {
+                return Err(MergeCheckError::PasswordFileIsNotFile(file.into()));
+            }
+        }
+        // check the input whose signature is kept (directories are checked when running)
+        if let Some(first) = self.keep_signature_of.clone()
+            && !self.input[0].is_dir()
+            && self.input[0] != first
+        {
+            return Err(MergeCheckError::KeptInputIsNotFirst(first));
+        }
+        // check signing keystore path
+        if let Some(keystore) = self.signing.as_ref().map(|s| s.keystore.clone())
+            && !keystore.is_file()
+        {
+            return Err(MergeCheckError::KeystoreIsNotFile(keystore));
+        }
+        // check page order file path
+        if let Some(file) = self.order.as_ref().and_then(|o| o.file.clone())
+            && !file.is_file()
+        {
+            return Err(MergeCheckError::OrderIsNotFile(file));
+        }
+        // check watermark image path
+        if let Some(image) = self.watermark.as_ref().and_then(|w| w.image.clone())
+            && !(image.is_file()
+                && image
+                    .extension()
+                    .is_some_and(|ext| ["png", "jpg", "jpeg"].contains(&&*ext.to_string_lossy())))
+        {
+            return Err(MergeCheckError::WatermarkImageIsNotSupported(image));
+        }
         // finally: return dir not exists or if override is allowed
         self.output
             .try_exists()
@@ -162,27 +800,48 @@ impl CheckableItem for Merge {
     }
 }
 
-/// Get PDF paths recursively + handle depth flag.
+/// Get PDF paths recursively + handle depth flag (every entry must resolve inside the sandbox
+/// `root`, if any, the symlinks are followed as `links` allows and the output is left out when
+/// found within directories).
 fn get_pdf_paths(
     paths: Vec<PathBuf>,
     cur_depth: usize,
     max_depth: &Depth,
+    root: Option<&Root>,
+    links: &mut Links,
+    generated: &Generated,
 ) -> Result<Vec<PathBuf>, MergeRunError> {
-    if let Depth::Max(deep) = max_depth {
-        if cur_depth > *deep {
-            return Ok(Vec::new());
-        }
+    if let Depth::Max(deep) = max_depth
+        && cur_depth > *deep
+    {
+        return Ok(Vec::new());
     }
     let mut result = Vec::new();
 
     paths.into_iter().try_for_each(|entry| {
+        if !links.admits(&entry, cur_depth == 0) {
+            return Ok(());
+        }
         if !entry
             .try_exists()
             .map_err(|_| MergeRunError::CouldNotReadEntry(entry.clone()))?
         {
             return Err(MergeRunError::EntryDoesNotExists(entry));
         }
+        if root.is_some_and(|r| !r.contains(&entry)) {
+            return Err(MergeRunError::PathIsOutsideRoot(entry));
+        }
         if entry.is_file() && entry.extension().is_some_and(|ext| ext == "pdf") {
+            if cur_depth > 0 && generated.is_output(&entry) {
+                Printer::title(
+                    PrintableTag::Warning,
+                    Some(format!(
+                        "leaving `{}` out, it's the output",
+                        entry.display()
+                    )),
+                );
+                return Ok(());
+            }
             result.push(entry.as_path().to_path_buf());
         } else if entry.is_dir() {
             let mut recursive = Vec::new();
@@ -196,7 +855,8 @@ fn get_pdf_paths(
                     Err(_) => return Err(MergeRunError::CouldNotReadEntry(entry)),
                 }
             }
-            let recursive = get_pdf_paths(recursive, cur_depth + 1, max_depth)?;
+            let recursive =
+                get_pdf_paths(recursive, cur_depth + 1, max_depth, root, links, generated)?;
             recursive.into_iter().for_each(|p| result.push(p));
         }
         Ok(())
@@ -204,33 +864,95 @@ fn get_pdf_paths(
     Ok(result)
 }
 
-/// Generates a new [`lopdf::Document`] based on paths at `paths` slice.
+/// Loads the PDF file at `path` within the resource `limits` (encrypted ones are loaded only when
+/// the empty password works).
+fn load_pdf(path: &Path, limits: &Limits) -> Result<Document, MergeRunError> {
+    Passwords::default().load(path, &[], limits)
+}
+
+/// Generates a new [`lopdf::Document`] based on paths at `paths` slice (each one loaded by
+/// `load`, which may leave it out).
 ///
-/// Will return the [`Ok`] variant if the doc generate successes, or the suitable [`MergeRunError`]
-/// for the occasion.
+/// Will return the [`Ok`] variant (the document + where each of its pages came from) if the doc
+/// generate successes, or the suitable [`MergeRunError`] for the occasion.
 ///
 /// This code was copy + paste from the official documentation
 /// (https://docs.rs/lopdf/0.38.0/lopdf/index.html).
-fn gen_pdf(paths: &[PathBuf]) -> Result<Document, MergeRunError> {
+fn gen_pdf(
+    paths: &[PathBuf],
+    mut load: impl FnMut(&Path) -> Result<Option<Document>, MergeRunError>,
+    orient: Option<Orientation>,
+    blank: Option<&Blank>,
+    dedupe: bool,
+    sanitize: bool,
+    refuse_signed: bool,
+) -> Result<(Document, Vec<PageOrigin>, Vec<Report>), MergeRunError> {
     // Define a starting `max_id` (will be used as start index for object_ids).
     let mut max_id = 1;
     let mut pagenum = 1;
     // Collect all Documents Objects grouped by a map
     let mut documents_pages = BTreeMap::new();
     let mut documents_objects = BTreeMap::new();
+    let mut documents_origins = BTreeMap::new();
+    let mut blank_pages = Vec::new();
+    let mut duplicated_pages = Vec::new();
+    let mut active_content = Vec::new();
+    let mut digests = HashMap::new();
     let mut document = Document::with_version("1.5");
     let mut doc: Document;
     let mut first: bool;
 
     for p in paths {
         first = false;
-        doc = Document::load(p).map_err(|_| MergeRunError::CouldNotLoadInput(p.into()))?;
+        doc = match load(p)? {
+            Some(doc) => doc,
+            None => continue,
+        };
+        if signed::is_signed(&doc) {
+            if refuse_signed {
+                return Err(MergeRunError::InputIsSigned(p.into()));
+            }
+            Printer::title(
+                PrintableTag::Warning,
+                Some(format!(
+                    "merging invalidates the digital signature of `{}`",
+                    p.display()
+                )),
+            );
+        }
+        if sanitize {
+            active_content.extend(sanitize::apply(&mut doc, p));
+        }
         doc.renumber_objects_with(max_id);
         max_id = doc.max_id + 1;
+        let mut hasher = PageHasher::new(&doc);
         documents_pages.extend(
             doc.get_pages()
-                .into_values()
-                .map(|object_id| {
+                .into_iter()
+                .filter(|(page, object_id)| {
+                    // blank and duplicated pages are left out of the new "Kids"
+                    let origin = PageOrigin {
+                        file: p.into(),
+                        page: *page,
+                    };
+                    if blank.is_some_and(|b| b.is_blank(&doc, *object_id)) {
+                        blank_pages.push(origin.to_string());
+                        return false;
+                    }
+                    if dedupe {
+                        match digests.entry(hasher.page(*object_id)) {
+                            Entry::Occupied(first) => {
+                                duplicated_pages.push(format!("{origin}, same as {}", first.get()));
+                                return false;
+                            }
+                            Entry::Vacant(entry) => {
+                                entry.insert(origin);
+                            }
+                        }
+                    }
+                    true
+                })
+                .map(|(page, object_id)| {
                     if !first {
                         let bookmark = Bookmark::new(
                             format!("Page_{}", pagenum),
@@ -242,7 +964,19 @@ fn gen_pdf(paths: &[PathBuf]) -> Result<Document, MergeRunError> {
                         first = true;
                         pagenum += 1;
                     }
-                    (object_id, doc.get_object(object_id).unwrap().to_owned())
+                    documents_origins.insert(
+                        object_id,
+                        PageOrigin {
+                            file: p.into(),
+                            page,
+                        },
+                    );
+                    // inherited attributes are copied since the page will get a new parent
+                    let object = match page::inherited_page(&doc, object_id) {
+                        Some(dictionary) => Object::Dictionary(dictionary),
+                        None => doc.get_object(object_id).unwrap().to_owned(),
+                    };
+                    (object_id, object)
                 })
                 .collect::<BTreeMap<ObjectId, Object>>(),
         );
@@ -273,10 +1007,10 @@ fn gen_pdf(paths: &[PathBuf]) -> Result<Document, MergeRunError> {
                 // We have also to merge all dictionaries of the old and the new "Pages" object
                 if let Ok(dictionary) = object.as_dict() {
                     let mut dictionary = dictionary.clone();
-                    if let Some((_, ref object)) = pages_object {
-                        if let Ok(old_dictionary) = object.as_dict() {
-                            dictionary.extend(old_dictionary);
-                        }
+                    if let Some((_, ref object)) = pages_object
+                        && let Ok(old_dictionary) = object.as_dict()
+                    {
+                        dictionary.extend(old_dictionary);
                     }
 
                     pages_object = Some((
@@ -308,6 +1042,9 @@ fn gen_pdf(paths: &[PathBuf]) -> Result<Document, MergeRunError> {
         if let Ok(dictionary) = object.as_dict() {
             let mut dictionary = dictionary.clone();
             dictionary.set("Parent", pages_object.as_ref().unwrap().0);
+            if let Some(orient) = orient {
+                orient.apply(&document, &mut dictionary);
+            }
 
             document
                 .objects
@@ -363,15 +1100,27 @@ fn gen_pdf(paths: &[PathBuf]) -> Result<Document, MergeRunError> {
     document.adjust_zero_pages();
 
     // Set all bookmarks to the PDF Object tree then set the Outlines to the Bookmark content map.
-    if let Some(n) = document.build_outline() {
-        if let Ok(Object::Dictionary(dict)) = document.get_object_mut(catalog_object.0) {
-            dict.set("Outlines", Object::Reference(n));
-        }
+    if let Some(n) = document.build_outline()
+        && let Ok(Object::Dictionary(dict)) = document.get_object_mut(catalog_object.0)
+    {
+        dict.set("Outlines", Object::Reference(n));
     }
 
-    document.compress();
-
-    Ok(document)
+    let reports = vec![
+        Report {
+            title: "Removed blank pages".into(),
+            items: blank_pages,
+        },
+        Report {
+            title: "Removed duplicated pages".into(),
+            items: duplicated_pages,
+        },
+        Report {
+            title: "Removed active content".into(),
+            items: active_content,
+        },
+    ];
+    Ok((document, documents_origins.into_values().collect(), reports))
 }
 
 impl RunnableItem for Merge {
@@ -379,16 +1128,138 @@ impl RunnableItem for Merge {
     type Output = Result<RunSuccess, MergeRunError>;
     fn run_item(self) -> Result<RunSuccess, MergeRunError> {
         let now = Instant::now();
-        let input_queue = get_pdf_paths(self.input, 0, &self.depth)?;
-        let mut rep_set_aux = HashSet::new();
-        input_queue.iter().try_for_each(|x| {
-            if !rep_set_aux.insert(x) && !self.repetition {
-                Err(MergeRunError::PathRepetitionWithoutFlag(x.into()))
-            } else {
-                Ok(())
+        let root = match &self.root {
+            Some(dir) => Some(Root::open(dir).ok_or(MergeRunError::CouldNotReadEntry(dir.into()))?),
+            None => None,
+        };
+        let mut links = Links::new(self.follow_symlinks);
+        let generated = Generated::new(&self.output, self.keep_generated);
+        let mut input_queue = get_pdf_paths(
+            self.input.clone(),
+            0,
+            &self.depth,
+            root.as_ref(),
+            &mut links,
+            &generated,
+        )?;
+        if !self.repetition {
+            let mut rep_map_aux = HashMap::new();
+            input_queue.iter().try_for_each(|x| {
+                let id = self
+                    .dedupe_by
+                    .id(x)
+                    .map_err(|_| MergeRunError::CouldNotReadEntry(x.into()))?;
+                match rep_map_aux.insert(id, x) {
+                    Some(first) => Err(MergeRunError::PathRepetitionWithoutFlag(
+                        first.into(),
+                        x.into(),
+                    )),
+                    None => Ok(()),
+                }
+            })?;
+        }
+        let base = match &self.keep_signature_of {
+            Some(first) if input_queue.first() != Some(first) => {
+                return Err(MergeRunError::KeptPathIsNotFirst(first.into()));
             }
-        })?;
-        let mut doc_file = gen_pdf(&input_queue)?;
+            Some(first) => Some(SignedBase::load(first, &self.limits)?),
+            None => None,
+        };
+        let kept_pages = base.as_ref().map_or(0, SignedBase::page_count);
+        let listed_passwords = self.passwords.listed()?;
+        let mut left_out = Vec::new();
+        let (mut doc_file, mut origins, mut reports) = gen_pdf(
+            &input_queue[usize::from(base.is_some())..],
+            |path| {
+                let doc = self.passwords.load(path, &listed_passwords, &self.limits)?;
+                // the files found within directories are told once loaded
+                if !self.input.iter().any(|p| p == path) && generated.is_left_out(&doc) {
+                    Printer::title(
+                        PrintableTag::Warning,
+                        Some(format!(
+                            "leaving `{}` out, it was generated by an earlier run",
+                            path.display()
+                        )),
+                    );
+                    left_out.push(path.to_path_buf());
+                    return Ok(None);
+                }
+                Ok(Some(doc))
+            },
+            self.orient,
+            self.blank.as_ref(),
+            self.dedupe_pages,
+            self.sanitize,
+            self.refuse_signed,
+        )?;
+        input_queue.retain(|path| !left_out.contains(path));
+        reports.insert(
+            0,
+            Report {
+                title: "Skipped symlinks".into(),
+                items: links.skipped,
+            },
+        );
+        if let Some(order) = &self.order {
+            order.apply(&mut doc_file, &mut origins)?;
+        }
+        if self.flatten {
+            reports.push(flatten::apply(&mut doc_file, &origins)?);
+        }
+        if let Some(crop) = &self.crop {
+            reports.push(crop.apply(&mut doc_file, &origins)?);
+        }
+        if let Some(page_size) = &self.page_size {
+            reports.push(page_size.apply(&mut doc_file, &origins)?);
+        }
+        if let Some(margin) = self.margin {
+            crop::add_margin(&mut doc_file, margin)?;
+        }
+        if let Some(underlay) = &self.underlay {
+            stamp::apply(&mut doc_file, underlay, Layer::Under, &self.limits)?;
+        }
+        if let Some(overlay) = &self.overlay {
+            stamp::apply(&mut doc_file, overlay, Layer::Over, &self.limits)?;
+        }
+        if let Some(nup) = &self.nup {
+            nup.apply(&mut doc_file, &mut origins)?;
+        }
+        if let Some(booklet) = &self.booklet {
+            booklet.apply(&mut doc_file, &mut origins)?;
+        }
+        if let Some(tile) = &self.tile {
+            tile.apply(&mut doc_file, &mut origins)?;
+        }
+        if let Some(watermark) = &self.watermark {
+            watermark.apply(&mut doc_file)?;
+        }
+        if let Some(bates) = &self.bates {
+            bates.stamp(&mut doc_file, kept_pages as usize)?;
+        }
+        if let Some(print_marks) = &self.print_marks {
+            print_marks.apply(&mut doc_file)?;
+        }
+        // anonymized outputs don't tell the tool that generated them
+        match self.anonymize {
+            true => {
+                anonymize::apply(&mut doc_file);
+                Printer::title(
+                    PrintableTag::Warning,
+                    Some(
+                        "the anonymized output isn't marked as generated, later runs merge it back when found within an input directory",
+                    ),
+                );
+            }
+            false => provenance::mark(&mut doc_file),
+        }
+        doc_file.compress();
+        if let Some(encryption) = &self.encryption {
+            encryption.apply(&mut doc_file)?;
+        }
+        let signer = match &self.signing {
+            Some(signing) => Some(signing.signer(doc_file.get_pages().len() as u32 + kept_pages)?),
+            None => None,
+        };
         if self.output.exists() {
             Printer::title(
                 PrintableTag::Warning,
@@ -396,22 +1267,42 @@ impl RunnableItem for Merge {
             );
         }
 
-        if let Some(parent) = self.output.parent() {
-            if !self.output.exists() {
-                fs::create_dir_all(parent)
+        if let Some(parent) = self.output.parent()
+            && !self.output.exists()
+        {
+            fs::create_dir_all(parent)
+                .map_err(|_| MergeRunError::CouldNotSaveTheOutput(self.output.clone()))?;
+        }
+        match base {
+            Some(base) => base.append(doc_file, &self.output)?,
+            None => {
+                doc_file
+                    .save(&self.output)
                     .map_err(|_| MergeRunError::CouldNotSaveTheOutput(self.output.clone()))?;
             }
         }
-        doc_file
-            .save(&self.output)
-            .map(|_| {
-                RunSuccess::new(
-                    input_queue,
-                    now.elapsed().as_secs_f64(),
-                    self.output.clone(),
-                )
-            })
-            .map_err(|_| MergeRunError::CouldNotSaveTheOutput(self.output))
+        if let Some(signer) = &signer {
+            signer.sign(&self.output)?;
+        }
+        if let Some(bates) = &self.bates {
+            // the pages of the kept input took the first labels
+            let kept = self.keep_signature_of.iter().flat_map(|file| {
+                (1..=kept_pages).map(|page| PageOrigin {
+                    file: file.clone(),
+                    page,
+                })
+            });
+            bates.write_log(&kept.chain(origins).collect::<Vec<_>>())?;
+        }
+        Ok(RunSuccess::new(
+            input_queue,
+            now.elapsed().as_secs_f64(),
+            self.output,
+            reports
+                .into_iter()
+                .filter(|r| !r.items.is_empty())
+                .collect(),
+        ))
     }
 
     #[allow(unused_variables)]
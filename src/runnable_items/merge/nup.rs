@@ -0,0 +1,189 @@
+use super::{
+    MergeRunError,
+    page::{self, Geometry, PageOrigin},
+    paper::PaperSize,
+};
+use lopdf::{
+    Document, Object, ObjectId, Stream,
+    content::{Content, Operation},
+    dictionary,
+};
+use std::{collections::BTreeMap, str::FromStr};
+
+/// Border line width.
+const BORDER_WIDTH: f32 = 0.5;
+
+/// N-up grid (`RxC`, rows x columns of a portrait sheet).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Grid {
+    pub rows: u32,
+    pub cols: u32,
+}
+
+impl FromStr for Grid {
+    type Err = ();
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (rows, cols) = s.to_lowercase().split_once('x').map_or(Err(()), |(r, c)| {
+            let parse = |n: &str| n.trim().parse::<u32>().ok().filter(|n| *n > 0).ok_or(());
+            Ok((parse(r)?, parse(c)?))
+        })?;
+        Ok(Self { rows, cols })
+    }
+}
+
+/// N-up layout options (several pages placed on a single sheet).
+#[derive(Debug, PartialEq)]
+pub struct NUp {
+    /// How to split the sheet.
+    pub grid: Grid,
+    /// Sheet size (the orientation that best fits the pages is used).
+    pub paper: PaperSize,
+    /// Space between the grid cells.
+    pub gutter: f32,
+    /// Draw a border around each placed page.
+    pub border: bool,
+}
+
+impl NUp {
+    /// Grid cell size for the given sheet size.
+    fn cell(&self, (width, height): (f32, f32), grid: Grid) -> (f32, f32) {
+        let (rows, cols) = (grid.rows as f32, grid.cols as f32);
+        (
+            (width - (cols - 1.0) * self.gutter) / cols,
+            (height - (rows - 1.0) * self.gutter) / rows,
+        )
+    }
+
+    /// Sheet size and grid for pages of the given size. The grid is given for a portrait sheet
+    /// and transposed on a landscape one (whichever makes the pages bigger), so `2x1` places the
+    /// pages side by side on a landscape sheet when they are portrait ones.
+    fn sheet(&self, page: (f32, f32)) -> ((f32, f32), Grid) {
+        let scale = |sheet, grid| {
+            let (cell_w, cell_h) = self.cell(sheet, grid);
+            (cell_w / page.0).min(cell_h / page.1)
+        };
+        let portrait = (self.paper.oriented(false), self.grid);
+        let landscape = (
+            self.paper.oriented(true),
+            Grid {
+                rows: self.grid.cols,
+                cols: self.grid.rows,
+            },
+        );
+        if scale(landscape.0, landscape.1) > scale(portrait.0, portrait.1) {
+            landscape
+        } else {
+            portrait
+        }
+    }
+
+    /// Places the document pages on the grid cells of new sheets (left to right, top to bottom).
+    /// Each page becomes a form XObject, scaled to fit its cell.
+    ///
+    /// The sheet origin is the origin of its first page.
+    pub fn apply(
+        &self,
+        doc: &mut Document,
+        origins: &mut Vec<PageOrigin>,
+    ) -> Result<(), MergeRunError> {
+        let err = |_| MergeRunError::CouldNotEditMergedPages("nup".into());
+        let pages = doc.page_iter().collect::<Vec<_>>();
+        let Some(first) = pages.first() else {
+            return Ok(());
+        };
+        let ((width, height), grid) = self.sheet(Geometry::of(doc, *first).visual_size());
+        let per_sheet = (grid.rows * grid.cols) as usize;
+        let (cell_w, cell_h) = self.cell((width, height), grid);
+
+        let mut sheets = Vec::new();
+        let mut replaced = BTreeMap::new();
+        for chunk in pages.chunks(per_sheet) {
+            let cells = chunk
+                .iter()
+                .enumerate()
+                .map(|(cell, page_id)| {
+                    let (row, col) = (cell as u32 / grid.cols, cell as u32 % grid.cols);
+                    let x = col as f32 * (cell_w + self.gutter);
+                    let y = height - (row + 1) as f32 * cell_h - row as f32 * self.gutter;
+                    (*page_id, [x, y, cell_w, cell_h])
+                })
+                .collect::<Vec<_>>();
+            let sheet = add_sheet(doc, (width, height), &cells, self.border).map_err(err)?;
+            chunk.iter().for_each(|id| {
+                replaced.insert(*id, sheet);
+            });
+            sheets.push(sheet);
+        }
+        page::set_pages(doc, sheets, &replaced).map_err(err)?;
+        *origins = origins.iter().step_by(per_sheet).cloned().collect();
+        Ok(())
+    }
+}
+
+/// Adds a new page of the given size where each page is drawn (as a form XObject) within its
+/// cell (`x`, `y`, `width`, `height`), scaled to fit and centered.
+///
+/// The new page isn't added to the page tree (see [`page::set_pages`]).
+pub fn add_sheet(
+    doc: &mut Document,
+    (width, height): (f32, f32),
+    cells: &[(ObjectId, [f32; 4])],
+    border: bool,
+) -> lopdf::Result<ObjectId> {
+    let mut xobjects = lopdf::Dictionary::new();
+    let mut operations = Vec::new();
+    for (index, (page_id, [x, y, cell_w, cell_h])) in cells.iter().enumerate() {
+        let geometry = Geometry::of(doc, *page_id);
+        let (w, h) = geometry.visual_size();
+        let scale = (cell_w / w).min(cell_h / h);
+        let x = x + (cell_w - w * scale) / 2.0;
+        let y = y + (cell_h - h * scale) / 2.0;
+        let name = format!("PdmersPage{index}");
+        let form = doc.add_object(page::form_xobject(doc, *page_id));
+        xobjects.set(name.as_bytes(), form);
+        operations.extend([
+            Operation::new("q", vec![]),
+            Operation::new(
+                "cm",
+                vec![
+                    scale.into(),
+                    0.into(),
+                    0.into(),
+                    scale.into(),
+                    x.into(),
+                    y.into(),
+                ],
+            ),
+            Operation::new(
+                "cm",
+                geometry
+                    .user_matrix()
+                    .into_iter()
+                    .map(Object::Real)
+                    .collect(),
+            ),
+            Operation::new("Do", vec![Object::Name(name.into_bytes())]),
+            Operation::new("Q", vec![]),
+        ]);
+        if border {
+            operations.extend([
+                Operation::new("q", vec![]),
+                Operation::new("w", vec![BORDER_WIDTH.into()]),
+                Operation::new(
+                    "re",
+                    vec![x.into(), y.into(), (w * scale).into(), (h * scale).into()],
+                ),
+                Operation::new("S", vec![]),
+                Operation::new("Q", vec![]),
+            ]);
+        }
+    }
+    let content = Content { operations }.encode()?;
+    let contents = doc.add_object(Stream::new(lopdf::Dictionary::new(), content));
+    Ok(doc.add_object(dictionary! {
+        "Type" => "Page",
+        "MediaBox" => vec![0.into(), 0.into(), width.into(), height.into()],
+        "Resources" => dictionary! { "XObject" => xobjects },
+        "Contents" => contents,
+    }))
+}
@@ -0,0 +1,179 @@
+use super::{
+    MergeRunError,
+    page::{self, PageOrigin},
+};
+use crate::utils::path::normalize_path_buf;
+use lopdf::{Document, Object, ObjectId};
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// How the final page sequence is built.
+#[derive(Debug, PartialEq)]
+pub struct PageOrder {
+    /// File listing the page references (`file.pdf:N`, one per line) in the wanted order.
+    pub file: Option<PathBuf>,
+    /// Reverse the page sequence (after the order file is applied).
+    pub reverse: bool,
+}
+
+/// Index of the merged page referenced by `reference` (`file.pdf:N`).
+///
+/// The file must be the input path or its trailing components (like the file name), as long as
+/// it points to a single input.
+fn resolve(origins: &[PageOrigin], reference: &str) -> Option<usize> {
+    let (file, number) = reference.rsplit_once(':')?;
+    let number = number.trim().parse::<u32>().ok()?;
+    let file = normalize_path_buf(Path::new(file.trim()));
+    let pages = origins
+        .iter()
+        .enumerate()
+        .filter(|(_, origin)| origin.page == number);
+    if let Some((index, _)) = pages.clone().find(|(_, origin)| origin.file == file) {
+        return Some(index);
+    }
+    let suffixed = pages
+        .filter(|(_, origin)| origin.file.ends_with(&file))
+        .collect::<Vec<_>>();
+    match suffixed.as_slice() {
+        [(index, first), rest @ ..] if rest.iter().all(|(_, o)| o.file == first.file) => {
+            Some(*index)
+        }
+        _ => None,
+    }
+}
+
+/// Keys of an annotation linking it to other annotations of the same page.
+const ANNOT_LINKS: [&[u8]; 3] = [b"Popup", b"Parent", b"IRT"];
+
+/// Gives the `copy` page its own copies of the annotations it shares with the page it was
+/// copied from, so each one points to its page (`/P`).
+///
+/// The links between the copied annotations (popups, replies) are redirected to the copies, and
+/// the copied widgets of a field are added to its kids (so they show the same value).
+fn copy_annots(doc: &mut Document, copy: ObjectId) -> lopdf::Result<()> {
+    let annots = doc
+        .get_dictionary(copy)?
+        .get(b"Annots")
+        .and_then(|a| doc.dereference(a))
+        .and_then(|(_, a)| a.as_array())
+        .cloned()
+        .unwrap_or_default();
+    if annots.is_empty() {
+        return Ok(());
+    }
+    let mut copies = HashMap::new();
+    let mut copied = Vec::new();
+    for object in annots {
+        let Ok(mut annot) = doc
+            .dereference(&object)
+            .and_then(|(_, a)| a.as_dict())
+            .cloned()
+        else {
+            copied.push(object);
+            continue;
+        };
+        annot.set("P", copy);
+        let id = doc.add_object(annot);
+        if let Object::Reference(original) = object {
+            copies.insert(original, id);
+        }
+        copied.push(id.into());
+    }
+    for id in copies.values().copied().collect::<Vec<_>>() {
+        let annot = doc.get_dictionary_mut(id)?;
+        for key in ANNOT_LINKS {
+            if let Ok(linked) = annot.get(key).and_then(Object::as_reference)
+                && let Some(linked) = copies.get(&linked)
+            {
+                annot.set(key, *linked);
+            }
+        }
+        // the parent is a field outside the page: the copy is another of its widgets
+        let parent = annot.get(b"Parent").and_then(Object::as_reference).ok();
+        if let Some(parent) = parent.filter(|parent| !copies.values().any(|c| c == parent))
+            && let Ok(field) = doc.get_dictionary_mut(parent)
+            && let Ok(kids) = field.get_mut(b"Kids").and_then(Object::as_array_mut)
+        {
+            kids.push(id.into());
+        }
+    }
+    doc.get_dictionary_mut(copy)?.set("Annots", copied);
+    Ok(())
+}
+
+impl PageOrder {
+    /// Page indexes listed by the order file (blank lines and `#` comments are skipped).
+    fn read(&self, file: &Path, origins: &[PageOrigin]) -> Result<Vec<usize>, MergeRunError> {
+        let text = fs::read_to_string(file)
+            .map_err(|_| MergeRunError::CouldNotReadOrderFile(file.into()))?;
+        let sequence = text
+            .lines()
+            .enumerate()
+            .map(|(index, line)| (index + 1, line.trim()))
+            .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+            .map(|(number, line)| {
+                resolve(origins, line)
+                    .ok_or_else(|| MergeRunError::InvalidPageReference(number, line.into()))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        match sequence.is_empty() {
+            true => Err(MergeRunError::EmptyPageOrder(file.into())),
+            false => Ok(sequence),
+        }
+    }
+
+    /// Rebuilds the page sequence from the order file (pages can be repeated or left out) and/or
+    /// reverses it.
+    ///
+    /// Repeated pages are copies sharing the same content, with their own annotations. References to the left out pages (like
+    /// bookmarks) are redirected to the next kept page.
+    pub fn apply(
+        &self,
+        doc: &mut Document,
+        origins: &mut Vec<PageOrigin>,
+    ) -> Result<(), MergeRunError> {
+        let err = |_| MergeRunError::CouldNotEditMergedPages("order".into());
+        let pages = doc.page_iter().collect::<Vec<_>>();
+        let mut sequence = match &self.file {
+            Some(file) => self.read(file, origins)?,
+            None => (0..pages.len()).collect(),
+        };
+        if self.reverse {
+            sequence.reverse();
+        }
+        let mut kept = HashSet::new();
+        let mut ordered = Vec::new();
+        let mut ordered_origins = Vec::new();
+        for index in sequence {
+            let page_id = match kept.insert(index) {
+                true => pages[index],
+                false => {
+                    let copy = doc.get_dictionary(pages[index]).map_err(err)?.clone();
+                    let copy = doc.add_object(copy);
+                    copy_annots(doc, copy).map_err(err)?;
+                    copy
+                }
+            };
+            ordered.push(page_id);
+            ordered_origins.push(origins[index].clone());
+        }
+        let mut replaced = BTreeMap::new();
+        let mut next = kept.iter().max().map(|index| pages[*index]);
+        for (index, page_id) in pages.iter().enumerate().rev() {
+            match kept.contains(&index) {
+                true => next = Some(*page_id),
+                false => {
+                    if let Some(next) = next {
+                        replaced.insert(*page_id, next);
+                    }
+                }
+            }
+        }
+        page::set_pages(doc, ordered, &replaced).map_err(err)?;
+        *origins = ordered_origins;
+        Ok(())
+    }
+}
@@ -0,0 +1,43 @@
+use super::page::Geometry;
+use lopdf::{Dictionary, Document};
+use std::str::FromStr;
+
+/// Page orientation.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Orientation {
+    Portrait,
+    Landscape,
+}
+
+impl FromStr for Orientation {
+    type Err = ();
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "portrait" => Ok(Self::Portrait),
+            "landscape" => Ok(Self::Landscape),
+            _ => Err(()),
+        }
+    }
+}
+
+impl Orientation {
+    /// Rotates the page (90 degrees, through its `Rotate` attribute) when its size after the
+    /// current rotation doesn't match this orientation. The page is turned back towards its
+    /// unrotated position (clockwise when it isn't rotated), so a page already turned a
+    /// quarter is undone instead of being turned upside down. Square pages are kept.
+    pub fn apply(&self, doc: &Document, page: &mut Dictionary) {
+        let geometry = Geometry::of_dict(doc, Some(page));
+        let (width, height) = geometry.visual_size();
+        let mismatch = match self {
+            Self::Portrait => width > height,
+            Self::Landscape => height > width,
+        };
+        if mismatch {
+            let rotate = match geometry.rotate {
+                90 | 180 => geometry.rotate - 90,
+                _ => (geometry.rotate + 90) % 360,
+            };
+            page.set("Rotate", rotate);
+        }
+    }
+}
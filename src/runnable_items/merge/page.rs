@@ -0,0 +1,515 @@
+//! # Page helpers
+//!
+//! Small [`lopdf`] helpers shared by the steps that edit the merged pages (geometry reading,
+//! resources handling and content stamping).
+use colored::Colorize;
+use lopdf::{
+    Dictionary, Document, Object, ObjectId, Stream,
+    content::{Content, Operation},
+    dictionary,
+};
+use std::{
+    collections::{BTreeMap, BTreeSet, HashSet},
+    path::PathBuf,
+    str::FromStr,
+};
+
+/// Page attributes that can be inherited from the page tree nodes.
+const INHERITABLE: [&[u8]; 4] = [b"Resources", b"MediaBox", b"CropBox", b"Rotate"];
+
+/// Default page box when the page doesn't provide one (US Letter).
+const LETTER: [f32; 4] = [0.0, 0.0, 612.0, 792.0];
+
+/// Where a merged page came from.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PageOrigin {
+    /// Input file path.
+    pub file: PathBuf,
+    /// Page number within the input file (starting at `1`).
+    pub page: u32,
+}
+
+impl std::fmt::Display for PageOrigin {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "`{}` (page {})",
+            self.file.to_string_lossy().cyan(),
+            self.page.to_string().cyan()
+        )
+    }
+}
+
+/// Clones the page dictionary copying the inheritable attributes from its page tree ancestors.
+///
+/// This is required since the merging process links every page directly to a new `Pages` node,
+/// so the attributes of the old nodes would be lost.
+pub fn inherited_page(doc: &Document, page_id: ObjectId) -> Option<Dictionary> {
+    let mut page = doc.get_dictionary(page_id).ok()?.clone();
+    let mut parent = page.get(b"Parent").and_then(Object::as_reference).ok();
+    let mut seen = HashSet::new();
+    while let Some(id) = parent.filter(|id| seen.insert(*id)) {
+        let Ok(node) = doc.get_dictionary(id) else {
+            break;
+        };
+        for key in INHERITABLE {
+            if let (false, Ok(value)) = (page.has(key), node.get(key)) {
+                page.set(key, value.clone());
+            }
+        }
+        parent = node.get(b"Parent").and_then(Object::as_reference).ok();
+    }
+    Some(page)
+}
+
+/// Reads a rectangle object (`[llx lly urx ury]`) normalizing its corners.
+pub fn rect(doc: &Document, object: &Object) -> Option<[f32; 4]> {
+    let (_, object) = doc.dereference(object).ok()?;
+    let values = object
+        .as_array()
+        .ok()?
+        .iter()
+        .map(|v| v.as_float().ok())
+        .collect::<Option<Vec<_>>>()?;
+    match values.as_slice() {
+        [x0, y0, x1, y1] => Some([x0.min(*x1), y0.min(*y1), x0.max(*x1), y0.max(*y1)]),
+        _ => None,
+    }
+}
+
+/// Page geometry as seen by a PDF viewer.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Geometry {
+    /// The visible page box (`CropBox` or `MediaBox`).
+    pub bbox: [f32; 4],
+    /// Page rotation (always `0`, `90`, `180` or `270`).
+    pub rotate: i64,
+}
+
+impl Geometry {
+    /// Reads the page geometry (defaults to US Letter when no box is found).
+    pub fn of(doc: &Document, page_id: ObjectId) -> Self {
+        Self::of_dict(doc, doc.get_dictionary(page_id).ok())
+    }
+
+    /// Same as [`Geometry::of`], but reading the given page dictionary.
+    pub fn of_dict(doc: &Document, page: Option<&Dictionary>) -> Self {
+        let read = |key: &[u8]| {
+            page.and_then(|p| p.get(key).ok())
+                .and_then(|o| rect(doc, o))
+        };
+        let bbox = read(b"CropBox")
+            .or_else(|| read(b"MediaBox"))
+            .unwrap_or(LETTER);
+        let rotate = page
+            .and_then(|p| p.get(b"Rotate").ok())
+            .and_then(|o| doc.dereference(o).ok())
+            .and_then(|(_, o)| o.as_i64().ok())
+            .unwrap_or(0);
+        Self {
+            bbox,
+            rotate: (rotate.rem_euclid(360) + 45) / 90 % 4 * 90,
+        }
+    }
+
+    /// Page size (`width`, `height`) after applying the rotation.
+    pub fn visual_size(&self) -> (f32, f32) {
+        let [x0, y0, x1, y1] = self.bbox;
+        match self.rotate {
+            90 | 270 => (y1 - y0, x1 - x0),
+            _ => (x1 - x0, y1 - y0),
+        }
+    }
+
+    /// Matrix (`cm` operands) that maps the page user space into the visual coordinates (the
+    /// inverse of [`Geometry::visual_matrix`]).
+    pub fn user_matrix(&self) -> [f32; 6] {
+        invert(self.visual_matrix())
+    }
+
+    /// Matrix (`cm` operands) that maps the visual coordinates (origin at the bottom-left corner
+    /// of the page as displayed) into the page user space.
+    pub fn visual_matrix(&self) -> [f32; 6] {
+        let [x0, y0, x1, y1] = self.bbox;
+        match self.rotate {
+            90 => [0.0, 1.0, -1.0, 0.0, x1, y0],
+            180 => [-1.0, 0.0, 0.0, -1.0, x1, y1],
+            270 => [0.0, -1.0, 1.0, 0.0, x0, y1],
+            _ => [1.0, 0.0, 0.0, 1.0, x0, y0],
+        }
+    }
+}
+
+/// Multiplies two affine matrices (`cm` operands), so the result applies `first` and then
+/// `second`.
+pub fn multiply(first: [f32; 6], second: [f32; 6]) -> [f32; 6] {
+    let ([a, b, c, d, e, f], [a2, b2, c2, d2, e2, f2]) = (first, second);
+    [
+        a * a2 + b * c2,
+        a * b2 + b * d2,
+        c * a2 + d * c2,
+        c * b2 + d * d2,
+        e * a2 + f * c2 + e2,
+        e * b2 + f * d2 + f2,
+    ]
+}
+
+/// Transforms a rectangle (`x0`, `y0`, `x1`, `y1`) by `matrix`, returning the rectangle that
+/// contains the transformed corners.
+pub fn transform_rect([a, b, c, d, e, f]: [f32; 6], [x0, y0, x1, y1]: [f32; 4]) -> [f32; 4] {
+    let corners = [(x0, y0), (x1, y0), (x0, y1), (x1, y1)]
+        .map(|(x, y)| (a * x + c * y + e, b * x + d * y + f));
+    let (xs, ys) = (corners.map(|(x, _)| x), corners.map(|(_, y)| y));
+    let min = |v: [f32; 4]| v.into_iter().fold(f32::INFINITY, f32::min);
+    let max = |v: [f32; 4]| v.into_iter().fold(f32::NEG_INFINITY, f32::max);
+    [min(xs), min(ys), max(xs), max(ys)]
+}
+
+/// Inverts an affine matrix (`cm` operands).
+pub fn invert([a, b, c, d, e, f]: [f32; 6]) -> [f32; 6] {
+    let det = a * d - b * c;
+    [
+        d / det,
+        -b / det,
+        -c / det,
+        a / det,
+        (c * f - d * e) / det,
+        (b * e - a * f) / det,
+    ]
+}
+
+/// Operations that draw crop marks outside the corners of `area` (`x0`, `y0`, `x1`, `y1`),
+/// starting `offset` points away from the corners (using the current stroke color).
+pub fn crop_marks([x0, y0, x1, y1]: [f32; 4], offset: f32, length: f32) -> Vec<Operation> {
+    let mut operations = vec![
+        Operation::new("q", vec![]),
+        Operation::new("w", vec![0.25.into()]),
+    ];
+    for (x, y, sx, sy) in [
+        (x0, y0, -1.0, -1.0),
+        (x1, y0, 1.0, -1.0),
+        (x0, y1, -1.0, 1.0),
+        (x1, y1, 1.0, 1.0),
+    ] {
+        let (near, far) = (offset, offset + length);
+        operations.extend([
+            Operation::new("m", vec![(x + sx * near).into(), y.into()]),
+            Operation::new("l", vec![(x + sx * far).into(), y.into()]),
+            Operation::new("m", vec![x.into(), (y + sy * near).into()]),
+            Operation::new("l", vec![x.into(), (y + sy * far).into()]),
+        ]);
+    }
+    operations.extend([Operation::new("S", vec![]), Operation::new("Q", vec![])]);
+    operations
+}
+
+/// Page tree root (`Pages` node referenced by the catalog).
+fn pages_root(doc: &Document) -> lopdf::Result<ObjectId> {
+    doc.catalog()?.get(b"Pages")?.as_reference()
+}
+
+/// Replaces the document pages by the given ones (in this order).
+///
+/// References to the `replaced` pages (outlines destinations, annotations, ...) are redirected to
+/// the new pages, then the objects no longer referenced (like the replaced pages) are removed.
+pub fn set_pages(
+    doc: &mut Document,
+    pages: Vec<ObjectId>,
+    replaced: &BTreeMap<ObjectId, ObjectId>,
+) -> lopdf::Result<()> {
+    let root = pages_root(doc)?;
+    for page_id in &pages {
+        doc.get_dictionary_mut(*page_id)?.set("Parent", root);
+    }
+    let node = doc.get_dictionary_mut(root)?;
+    node.set("Count", pages.len() as u32);
+    node.set(
+        "Kids",
+        pages.into_iter().map(Object::Reference).collect::<Vec<_>>(),
+    );
+    doc.traverse_objects(|object| {
+        if let Object::Reference(id) = object
+            && let Some(new) = replaced.get(id)
+        {
+            *id = *new;
+        }
+    });
+    doc.prune_objects();
+    Ok(())
+}
+
+/// Where to place an item within the page.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Anchor {
+    TopLeft,
+    TopCenter,
+    TopRight,
+    BottomLeft,
+    BottomCenter,
+    #[default]
+    BottomRight,
+}
+
+impl FromStr for Anchor {
+    type Err = ();
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "top-left" => Ok(Self::TopLeft),
+            "top-center" => Ok(Self::TopCenter),
+            "top-right" => Ok(Self::TopRight),
+            "bottom-left" => Ok(Self::BottomLeft),
+            "bottom-center" => Ok(Self::BottomCenter),
+            "bottom-right" => Ok(Self::BottomRight),
+            _ => Err(()),
+        }
+    }
+}
+
+impl Anchor {
+    /// Bottom-left corner of an `item` (`width`, `height`) placed within an `area` keeping the
+    /// `margin` from the page edges.
+    pub fn place(&self, area: (f32, f32), item: (f32, f32), margin: f32) -> (f32, f32) {
+        let left = margin;
+        let center = (area.0 - item.0) / 2.0;
+        let right = area.0 - item.0 - margin;
+        let top = area.1 - item.1 - margin;
+        let bottom = margin;
+        match self {
+            Self::TopLeft => (left, top),
+            Self::TopCenter => (center, top),
+            Self::TopRight => (right, top),
+            Self::BottomLeft => (left, bottom),
+            Self::BottomCenter => (center, bottom),
+            Self::BottomRight => (right, bottom),
+        }
+    }
+}
+
+/// Reads a dictionary that can be both direct or referenced.
+fn owned_dict(doc: &Document, object: Option<&Object>) -> Dictionary {
+    match object {
+        Some(Object::Reference(id)) => doc.get_dictionary(*id).cloned().unwrap_or_default(),
+        Some(Object::Dictionary(dict)) => dict.clone(),
+        _ => Dictionary::new(),
+    }
+}
+
+/// Adds `object` as `name` into the `category` (`Font`, `XObject`, ...) resources of the page.
+///
+/// The page gets its own resources dictionary, so other pages sharing the old one aren't
+/// affected.
+pub fn add_resource(
+    doc: &mut Document,
+    page_id: ObjectId,
+    category: &[u8],
+    name: &[u8],
+    object: Object,
+) -> lopdf::Result<()> {
+    let page = doc.get_dictionary(page_id)?;
+    let mut resources = owned_dict(doc, page.get(b"Resources").ok());
+    let mut entries = owned_dict(doc, resources.get(category).ok());
+    entries.set(name, object);
+    resources.set(category, entries);
+    doc.get_dictionary_mut(page_id)?.set("Resources", resources);
+    Ok(())
+}
+
+/// Current content streams of the page (as references).
+fn contents(doc: &Document, page_id: ObjectId) -> lopdf::Result<Vec<Object>> {
+    let page = doc.get_dictionary(page_id)?;
+    Ok(match page.get(b"Contents") {
+        Ok(Object::Reference(id)) => match doc.get_object(*id) {
+            Ok(Object::Array(arr)) => arr.clone(),
+            _ => vec![Object::Reference(*id)],
+        },
+        Ok(Object::Array(arr)) => arr.clone(),
+        _ => vec![],
+    })
+}
+
+/// Decoded page content (the content streams are joined with a line break, since a stream can end
+/// right after a token).
+pub fn content(doc: &Document, page_id: ObjectId) -> Vec<u8> {
+    doc.get_page_contents(page_id)
+        .into_iter()
+        .filter_map(|id| doc.get_object(id).and_then(Object::as_stream).ok())
+        .map(|stream| {
+            stream
+                .decompressed_content()
+                .unwrap_or_else(|_| stream.content.clone())
+        })
+        .collect::<Vec<_>>()
+        .join(b"\n".as_slice())
+}
+
+/// Turns the page into a form XObject (its bounding box is the visible page box).
+pub fn form_xobject(doc: &Document, page_id: ObjectId) -> Stream {
+    let resources = doc
+        .get_dictionary(page_id)
+        .ok()
+        .and_then(|page| page.get(b"Resources").ok())
+        .cloned()
+        .unwrap_or_else(|| Dictionary::new().into());
+    Stream::new(
+        dictionary! {
+            "Type" => "XObject",
+            "Subtype" => "Form",
+            "BBox" => Geometry::of(doc, page_id).bbox.into_iter().map(Object::Real).collect::<Vec<_>>(),
+            "Resources" => resources,
+        },
+        content(doc, page_id),
+    )
+}
+
+/// Collects the ids of all objects reachable from `object` (`Parent` links aren't followed, so
+/// the whole page tree isn't collected).
+pub fn references(doc: &Document, object: &Object, found: &mut BTreeSet<ObjectId>) {
+    match object {
+        Object::Reference(id) => {
+            if found.insert(*id)
+                && let Ok(object) = doc.get_object(*id)
+            {
+                references(doc, object, found);
+            }
+        }
+        Object::Array(arr) => arr.iter().for_each(|o| references(doc, o, found)),
+        Object::Dictionary(dict) | Object::Stream(Stream { dict, .. }) => dict
+            .iter()
+            .filter(|(key, _)| key.as_slice() != b"Parent")
+            .for_each(|(_, o)| references(doc, o, found)),
+        _ => {}
+    }
+}
+
+/// Draws `content` over the page content. The old content is wrapped within a `q`/`Q` pair, so
+/// the graphics state left by it doesn't affect the new one.
+pub fn stamp_over(doc: &mut Document, page_id: ObjectId, content: Vec<u8>) -> lopdf::Result<()> {
+    let mut streams = contents(doc, page_id)?;
+    let save = doc.add_object(Stream::new(Dictionary::new(), b"q\n".to_vec()));
+    let stamp = doc.add_object(Stream::new(
+        Dictionary::new(),
+        [b"\nQ\n".as_slice(), &content].concat(),
+    ));
+    streams.insert(0, Object::Reference(save));
+    streams.push(Object::Reference(stamp));
+    doc.get_dictionary_mut(page_id)?.set("Contents", streams);
+    Ok(())
+}
+
+/// Draws `content` under the page content (the `content` must restore its own graphics state).
+pub fn stamp_under(doc: &mut Document, page_id: ObjectId, content: Vec<u8>) -> lopdf::Result<()> {
+    let mut streams = contents(doc, page_id)?;
+    let stamp = doc.add_object(Stream::new(
+        Dictionary::new(),
+        [content, b"\n".to_vec()].concat(),
+    ));
+    streams.insert(0, Object::Reference(stamp));
+    doc.get_dictionary_mut(page_id)?.set("Contents", streams);
+    Ok(())
+}
+
+/// Page boxes dropped by [`transform`] (their coordinates no longer apply).
+const BOXES: [&[u8]; 4] = [b"CropBox", b"BleedBox", b"TrimBox", b"ArtBox"];
+
+/// Transforms the page content (and its annotations) by `matrix`, setting `media_box` as the new
+/// page box. The content is clipped to the old visible page box.
+///
+/// The page rotation is dropped, so `matrix` must take it into account (like
+/// [`Geometry::user_matrix`] does).
+pub fn transform(
+    doc: &mut Document,
+    page_id: ObjectId,
+    matrix: [f32; 6],
+    media_box: [f32; 4],
+) -> lopdf::Result<()> {
+    let [x0, y0, x1, y1] = Geometry::of(doc, page_id).bbox;
+    let mut streams = contents(doc, page_id)?;
+    let cm = Content {
+        operations: vec![
+            Operation::new("q", vec![]),
+            Operation::new("cm", matrix.map(Object::Real).to_vec()),
+            Operation::new(
+                "re",
+                vec![x0.into(), y0.into(), (x1 - x0).into(), (y1 - y0).into()],
+            ),
+            Operation::new("W", vec![]),
+            Operation::new("n", vec![]),
+        ],
+    };
+    let save = doc.add_object(Stream::new(Dictionary::new(), cm.encode()?));
+    let restore = doc.add_object(Stream::new(Dictionary::new(), b"\nQ\n".to_vec()));
+    streams.insert(0, Object::Reference(save));
+    streams.push(Object::Reference(restore));
+    let annots = doc
+        .get_dictionary(page_id)?
+        .get(b"Annots")
+        .and_then(|a| doc.dereference(a))
+        .and_then(|(_, a)| a.as_array())
+        .cloned()
+        .unwrap_or_default();
+    for annot in annots {
+        let Object::Reference(id) = annot else {
+            continue;
+        };
+        let Some(bbox) = doc
+            .get_dictionary(id)
+            .ok()
+            .and_then(|a| a.get(b"Rect").ok())
+            .and_then(|r| rect(doc, r))
+        else {
+            continue;
+        };
+        doc.get_dictionary_mut(id)?.set(
+            "Rect",
+            transform_rect(matrix, bbox)
+                .into_iter()
+                .map(Object::Real)
+                .collect::<Vec<_>>(),
+        );
+    }
+    let page = doc.get_dictionary_mut(page_id)?;
+    page.set("Contents", streams);
+    page.set(
+        "MediaBox",
+        media_box.into_iter().map(Object::Real).collect::<Vec<_>>(),
+    );
+    page.remove(b"Rotate");
+    BOXES.iter().for_each(|key| {
+        page.remove(key);
+    });
+    Ok(())
+}
+
+/// A selection of page numbers (`1-3,5,8-`).
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct PageSelection(Vec<(u32, Option<u32>)>);
+
+impl FromStr for PageSelection {
+    type Err = ();
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parse = |n: &str| n.trim().parse::<u32>().ok().filter(|n| *n > 0).ok_or(());
+        s.split(',')
+            .map(|range| match range.split_once('-') {
+                Some((first, "")) => Ok((parse(first)?, None)),
+                Some((first, last)) => match (parse(first)?, parse(last)?) {
+                    (first, last) if first <= last => Ok((first, Some(last))),
+                    _ => Err(()),
+                },
+                None => parse(range).map(|n| (n, Some(n))),
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .map(Self)
+    }
+}
+
+impl PageSelection {
+    /// Tests if the page `number` (starting at `1`) is selected. An empty selection selects all
+    /// pages.
+    pub fn contains(&self, number: u32) -> bool {
+        self.0.is_empty()
+            || self
+                .0
+                .iter()
+                .any(|(first, last)| number >= *first && last.is_none_or(|l| number <= l))
+    }
+}
@@ -0,0 +1,89 @@
+use super::{
+    MergeRunError, Report,
+    page::{self, Geometry, PageOrigin},
+    paper::PaperSize,
+};
+use colored::Colorize;
+use lopdf::Document;
+use std::str::FromStr;
+
+/// Differences (in points) below this value are ignored when comparing page sizes.
+const TOLERANCE: f32 = 0.5;
+
+/// How the page content is scaled into the new page size.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Scaling {
+    /// Scale to fit the whole content within the page.
+    #[default]
+    Fit,
+    /// Scale to fill the whole page (the content overflow is cut).
+    Fill,
+    /// Keep the content size.
+    None,
+}
+
+impl FromStr for Scaling {
+    type Err = ();
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "fit" => Ok(Self::Fit),
+            "fill" => Ok(Self::Fill),
+            "none" => Ok(Self::None),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Page size normalization options.
+#[derive(Debug, PartialEq)]
+pub struct PageSize {
+    /// The new page size (oriented as each page).
+    pub paper: PaperSize,
+    /// How the content is scaled.
+    pub scaling: Scaling,
+}
+
+impl PageSize {
+    /// Resizes every page to the paper size (keeping the page orientation) scaling and centering
+    /// its content. Returns the report of the resized pages (with their original size).
+    pub fn apply(
+        &self,
+        doc: &mut Document,
+        origins: &[PageOrigin],
+    ) -> Result<Report, MergeRunError> {
+        let err = |_| MergeRunError::CouldNotEditMergedPages("page size".into());
+        let mut items = Vec::new();
+        for ((_, page_id), origin) in doc.get_pages().into_iter().zip(origins) {
+            let geometry = Geometry::of(doc, page_id);
+            let (w, h) = geometry.visual_size();
+            let (width, height) = self.paper.oriented(w > h);
+            if (width - w).abs() < TOLERANCE && (height - h).abs() < TOLERANCE {
+                continue;
+            }
+            let scale = match self.scaling {
+                Scaling::Fit => (width / w).min(height / h),
+                Scaling::Fill => (width / w).max(height / h),
+                Scaling::None => 1.0,
+            };
+            let placement = [
+                scale,
+                0.0,
+                0.0,
+                scale,
+                (width - w * scale) / 2.0,
+                (height - h * scale) / 2.0,
+            ];
+            let matrix = page::multiply(geometry.user_matrix(), placement);
+            page::transform(doc, page_id, matrix, [0.0, 0.0, width, height]).map_err(err)?;
+            items.push(format!(
+                "{origin}: {} x {} pt",
+                w.round().to_string().cyan(),
+                h.round().to_string().cyan()
+            ));
+        }
+        Ok(Report {
+            title: "Resized pages (original size)".into(),
+            items,
+        })
+    }
+}
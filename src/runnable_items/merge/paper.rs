@@ -0,0 +1,62 @@
+use std::str::FromStr;
+
+/// Points per millimeter.
+const MM: f32 = 72.0 / 25.4;
+/// Points per inch.
+const INCH: f32 = 72.0;
+
+/// A paper size (in points, portrait oriented).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PaperSize {
+    pub width: f32,
+    pub height: f32,
+}
+
+impl Default for PaperSize {
+    /// A4 paper size.
+    fn default() -> Self {
+        Self {
+            width: 210.0 * MM,
+            height: 297.0 * MM,
+        }
+    }
+}
+
+impl FromStr for PaperSize {
+    type Err = ();
+    /// Parses a paper name (`A3`, `A4`, `A5`, `Letter`, `Legal`, `Tabloid`) or a custom size
+    /// (`WxH` followed by `mm`, `in` or `pt`, which is the default unit).
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim().to_lowercase();
+        let (width, height) = match s.as_str() {
+            "a3" => (297.0 * MM, 420.0 * MM),
+            "a4" => (210.0 * MM, 297.0 * MM),
+            "a5" => (148.0 * MM, 210.0 * MM),
+            "letter" => (8.5 * INCH, 11.0 * INCH),
+            "legal" => (8.5 * INCH, 14.0 * INCH),
+            "tabloid" => (11.0 * INCH, 17.0 * INCH),
+            custom => {
+                let (size, unit) = [("mm", MM), ("in", INCH), ("pt", 1.0)]
+                    .into_iter()
+                    .find_map(|(suffix, unit)| custom.strip_suffix(suffix).map(|s| (s, unit)))
+                    .unwrap_or((custom, 1.0));
+                let (w, h) = size.split_once('x').ok_or(())?;
+                let parse = |n: &str| n.trim().parse::<f32>().ok().filter(|n| *n > 0.0).ok_or(());
+                (parse(w)? * unit, parse(h)? * unit)
+            }
+        };
+        Ok(Self { width, height })
+    }
+}
+
+impl PaperSize {
+    /// Paper size (`width`, `height`) in the landscape or portrait orientation.
+    pub fn oriented(&self, landscape: bool) -> (f32, f32) {
+        let (short, long) = (self.width.min(self.height), self.width.max(self.height));
+        if landscape {
+            (long, short)
+        } else {
+            (short, long)
+        }
+    }
+}
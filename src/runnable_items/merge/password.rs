@@ -0,0 +1,108 @@
+use super::{MergeRunError, limits::Limits};
+use lopdf::{Document, Object};
+use std::{
+    collections::HashMap,
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+/// Input suffix that names the environment variable holding the input password.
+pub const ENV_SUFFIX: &str = "#password-env=";
+
+/// Where the passwords of the encrypted inputs come from.
+#[derive(Debug, Default, PartialEq)]
+pub struct Passwords {
+    /// Environment variables holding the password of an input (a directory one applies to every
+    /// file within it).
+    pub env: HashMap<PathBuf, String>,
+    /// File listing candidate passwords (one per line), tried on every encrypted input.
+    pub file: Option<PathBuf>,
+    /// Prompt (without echo) for the passwords still missing.
+    pub prompt: bool,
+}
+
+/// Whether the document is encrypted and couldn't be decrypted (only the empty password is tried
+/// when loading).
+fn is_locked(doc: &Document) -> bool {
+    doc.is_encrypted() && doc.encryption_state.is_none()
+}
+
+/// Drops the encryption dictionary of a decrypted document (its objects are already decrypted,
+/// so it no longer applies).
+fn decrypted(mut doc: Document) -> Document {
+    if let Ok(id) = doc.trailer.get(b"Encrypt").and_then(Object::as_reference) {
+        doc.objects.remove(&id);
+    }
+    doc.trailer.remove(b"Encrypt");
+    doc.encryption_state = None;
+    doc
+}
+
+/// Loads the encrypted PDF `bytes` with `password` ([`None`] if it's the wrong one).
+fn unlock(bytes: &[u8], password: &str) -> Option<Document> {
+    Document::load_mem_with_password(bytes, password)
+        .ok()
+        .filter(|doc| !is_locked(doc))
+        .map(decrypted)
+}
+
+impl Passwords {
+    /// Candidate passwords listed by the password file (blank lines are skipped).
+    pub fn listed(&self) -> Result<Vec<String>, MergeRunError> {
+        let Some(file) = &self.file else {
+            return Ok(Vec::new());
+        };
+        let text = fs::read_to_string(file)
+            .map_err(|_| MergeRunError::CouldNotReadPasswordFile(file.into()))?;
+        Ok(text
+            .lines()
+            .filter(|line| !line.is_empty())
+            .map(String::from)
+            .collect())
+    }
+
+    /// Loads the PDF file at `path` within the resource `limits`. When it's encrypted, the input
+    /// password is used, else the `listed` ones are tried and then the user is prompted (if
+    /// allowed).
+    pub fn load(
+        &self,
+        path: &Path,
+        listed: &[String],
+        limits: &Limits,
+    ) -> Result<Document, MergeRunError> {
+        let bytes = limits.read(path)?;
+        let doc = self.open(path, &bytes, listed)?;
+        limits.check(&doc, path)?;
+        Ok(doc)
+    }
+
+    /// Parses the `bytes` of the PDF file at `path`, decrypting them if needed.
+    fn open(
+        &self,
+        path: &Path,
+        bytes: &[u8],
+        listed: &[String],
+    ) -> Result<Document, MergeRunError> {
+        let doc =
+            Document::load_mem(bytes).map_err(|_| MergeRunError::CouldNotLoadInput(path.into()))?;
+        if !is_locked(&doc) {
+            return Ok(decrypted(doc));
+        }
+        let wrong = || MergeRunError::WrongInputPassword(path.into());
+        if let Some(var) = path.ancestors().find_map(|dir| self.env.get(dir)) {
+            let password =
+                env::var(var).map_err(|_| MergeRunError::PasswordEnvIsNotSet(var.into()))?;
+            return unlock(bytes, &password).ok_or_else(wrong);
+        }
+        if let Some(doc) = listed.iter().find_map(|password| unlock(bytes, password)) {
+            return Ok(doc);
+        }
+        if self.prompt {
+            let password =
+                rpassword::prompt_password(format!("Password for `{}`: ", path.display()))
+                    .map_err(|_| MergeRunError::InputIsEncrypted(path.into()))?;
+            return unlock(bytes, &password).ok_or_else(wrong);
+        }
+        Err(MergeRunError::InputIsEncrypted(path.into()))
+    }
+}
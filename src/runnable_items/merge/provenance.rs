@@ -0,0 +1,62 @@
+use super::identity::{DedupeBy, FileId};
+use lopdf::{Dictionary, Document, Object};
+use std::path::Path;
+
+/// Private key set in the outputs Info dictionary, telling the files generated by pdmers (the
+/// producer of the inputs is left as it is).
+const MARKER: &[u8] = b"PdmersGenerated";
+
+/// Marks the document as generated by pdmers, setting the private marker key of its Info
+/// dictionary (a new one is added if the document has none).
+pub fn mark(doc: &mut Document) {
+    let info = doc
+        .trailer
+        .get(b"Info")
+        .and_then(Object::as_reference)
+        .ok()
+        .filter(|id| doc.objects.contains_key(id));
+    match info.and_then(|id| doc.get_dictionary_mut(id).ok()) {
+        Some(info) => info.set(MARKER, true),
+        None => {
+            let id = doc.add_object(Dictionary::from_iter([(MARKER, true.into())]));
+            doc.trailer.set("Info", id);
+        }
+    }
+}
+
+/// Files left out of the input directories: the output itself and, unless they're kept, the
+/// files generated by earlier runs (so the output doesn't swallow its previous versions).
+pub struct Generated {
+    output: Option<FileId>,
+    keep: bool,
+}
+
+impl Generated {
+    /// Files generated by pdmers, `output` included (the ones generated by earlier runs are
+    /// merged anyway when `keep` is set).
+    pub fn new(output: &Path, keep: bool) -> Self {
+        Self {
+            output: DedupeBy::Inode.id(output).ok(),
+            keep,
+        }
+    }
+
+    /// Whether the file at `path` is the output.
+    pub fn is_output(&self, path: &Path) -> bool {
+        self.output.is_some() && DedupeBy::Inode.id(path).ok() == self.output
+    }
+
+    /// Whether the loaded `doc` is left out, generated by an earlier run (told by the marker key of
+    /// its Info dictionary once it's loaded, so the input isn't parsed twice).
+    pub fn is_left_out(&self, doc: &Document) -> bool {
+        !self.keep
+            && doc
+                .trailer
+                .get(b"Info")
+                .and_then(|info| doc.dereference(info))
+                .and_then(|(_, info)| info.as_dict())
+                .and_then(|info| info.get(MARKER))
+                .and_then(Object::as_bool)
+                .unwrap_or(false)
+    }
+}
@@ -0,0 +1,33 @@
+use std::path::{Path, PathBuf};
+
+/// Sandbox directory every input and output must resolve inside (following symlinks).
+#[derive(Debug, PartialEq)]
+pub struct Root(PathBuf);
+
+/// Where `path` resolves once its symlinks are followed, even when it doesn't exist yet (like
+/// the output). Dangling symlinks don't resolve, since writing through them could land anywhere.
+fn resolve(path: &Path) -> Option<PathBuf> {
+    if let Ok(resolved) = path.canonicalize() {
+        return Some(resolved);
+    }
+    if path.symlink_metadata().is_ok() {
+        return None;
+    }
+    let parent = path
+        .parent()
+        .filter(|p| !p.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+    Some(resolve(parent)?.join(path.file_name()?))
+}
+
+impl Root {
+    /// Opens the sandbox at `dir` ([`None`] if it isn't an existing directory).
+    pub fn open(dir: &Path) -> Option<Self> {
+        dir.canonicalize().ok().filter(|d| d.is_dir()).map(Self)
+    }
+
+    /// Whether `path` resolves inside the sandbox.
+    pub fn contains(&self, path: &Path) -> bool {
+        resolve(path).is_some_and(|p| p.starts_with(&self.0))
+    }
+}
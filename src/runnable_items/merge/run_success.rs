@@ -2,21 +2,30 @@ use crate::utils::print::PrintableItem;
 use colored::Colorize;
 use std::path::PathBuf;
 
+/// A titled list of details about the merged pages (resized pages, removed pages, ...).
+#[derive(Clone)]
+pub struct Report {
+    pub title: String,
+    pub items: Vec<String>,
+}
+
 /// Struct to report merging success.
 #[derive(Clone)]
 pub struct RunSuccess {
     pub files: Vec<PathBuf>,
     pub seconds: f64,
     pub output: PathBuf,
+    pub reports: Vec<Report>,
 }
 
 impl RunSuccess {
     /// Generate a new [`RunSuccess`].
-    pub fn new(files: Vec<PathBuf>, seconds: f64, output: PathBuf) -> Self {
+    pub fn new(files: Vec<PathBuf>, seconds: f64, output: PathBuf, reports: Vec<Report>) -> Self {
         Self {
             files,
             seconds,
             output,
+            reports,
         }
     }
 }
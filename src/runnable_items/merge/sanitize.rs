@@ -0,0 +1,205 @@
+use colored::Colorize;
+use lopdf::{Dictionary, Document, Object, ObjectId};
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::Path,
+};
+
+/// Actions that run code, open other files or send data out.
+const UNSAFE_ACTIONS: [&[u8]; 4] = [b"JavaScript", b"Launch", b"SubmitForm", b"ImportData"];
+/// Annotations that play media or carry files.
+const UNSAFE_ANNOTATIONS: [&[u8]; 2] = [b"RichMedia", b"FileAttachment"];
+
+/// Kind of the action dictionary, if it's an unsafe one.
+fn unsafe_action(dict: &Dictionary) -> Option<String> {
+    dict.get(b"S")
+        .and_then(Object::as_name)
+        .ok()
+        .filter(|s| UNSAFE_ACTIONS.contains(s))
+        .map(|s| format!("{} action", String::from_utf8_lossy(s)))
+}
+
+/// Kind of the (direct or referenced) action, if it's an unsafe one.
+fn action_kind(object: &Object, referenced: &HashMap<ObjectId, String>) -> Option<String> {
+    match object {
+        Object::Reference(id) => referenced.get(id).cloned(),
+        Object::Dictionary(dict) => unsafe_action(dict),
+        _ => None,
+    }
+}
+
+/// Removes the unsafe actions (and every additional actions dictionary, associated files list and
+/// embedded file) found within `object`.
+fn strip(object: &mut Object, referenced: &HashMap<ObjectId, String>, removed: &mut Vec<String>) {
+    let dict = match object {
+        Object::Dictionary(dict) => dict,
+        Object::Stream(stream) => &mut stream.dict,
+        Object::Array(array) => {
+            array
+                .iter_mut()
+                .for_each(|item| strip(item, referenced, removed));
+            return;
+        }
+        _ => return,
+    };
+    if dict.remove(b"AA").is_some() {
+        removed.push("additional actions (/AA)".into());
+    }
+    if dict.remove(b"AF").is_some() {
+        removed.push("associated files (/AF)".into());
+    }
+    if dict.remove(b"EF").is_some() {
+        removed.push("embedded files (/EF)".into());
+    }
+    for key in [b"A".as_slice(), b"OpenAction", b"Next"] {
+        match dict.get_mut(key) {
+            Ok(Object::Array(next)) => {
+                next.retain(|action| match action_kind(action, referenced) {
+                    Some(kind) => {
+                        removed.push(kind);
+                        false
+                    }
+                    None => true,
+                })
+            }
+            Ok(action) => {
+                if let Some(kind) = action_kind(action, referenced) {
+                    removed.push(kind);
+                    dict.remove(key);
+                }
+            }
+            Err(_) => {}
+        }
+    }
+    dict.iter_mut()
+        .for_each(|(_, value)| strip(value, referenced, removed));
+}
+
+/// Mutable (direct or referenced) dictionary stored at `key` within the `parent` object.
+fn child_dict<'a>(
+    doc: &'a mut Document,
+    parent: ObjectId,
+    key: &[u8],
+) -> Option<&'a mut Dictionary> {
+    let child = doc.get_dictionary(parent).ok()?.get(key).ok()?;
+    match child {
+        Object::Reference(id) => {
+            let id = *id;
+            doc.get_dictionary_mut(id).ok()
+        }
+        _ => doc
+            .get_dictionary_mut(parent)
+            .ok()?
+            .get_mut(key)
+            .ok()?
+            .as_dict_mut()
+            .ok(),
+    }
+}
+
+/// Strips the active content of the input: document and page JavaScript, open and additional
+/// actions, `Launch`/`SubmitForm`/`ImportData` actions, embedded and associated files (wherever
+/// they hang), RichMedia and XFA.
+///
+/// Returns what was removed (each kind with its count) to be listed in the report.
+pub fn apply(doc: &mut Document, file: &Path) -> Vec<String> {
+    let mut removed = Vec::new();
+    let Ok(root) = doc.trailer.get(b"Root").and_then(Object::as_reference) else {
+        return removed;
+    };
+    if let Ok(catalog) = doc.get_dictionary_mut(root)
+        && catalog.remove(b"OpenAction").is_some()
+    {
+        removed.push("document open action".into());
+    }
+    if let Some(names) = child_dict(doc, root, b"Names") {
+        if names.remove(b"JavaScript").is_some() {
+            removed.push("document JavaScript".into());
+        }
+        if names.remove(b"EmbeddedFiles").is_some() {
+            removed.push("embedded files".into());
+        }
+    }
+    if let Some(form) = child_dict(doc, root, b"AcroForm")
+        && form.remove(b"XFA").is_some()
+    {
+        removed.push("XFA form".into());
+    }
+    for page_id in doc.page_iter().collect::<Vec<_>>() {
+        let Some(annots) = doc
+            .get_dictionary(page_id)
+            .and_then(|p| p.get(b"Annots"))
+            .and_then(|a| doc.dereference(a))
+            .and_then(|(_, a)| a.as_array())
+            .ok()
+            .cloned()
+        else {
+            continue;
+        };
+        let count = annots.len();
+        let kept = annots
+            .into_iter()
+            .filter(|annot| {
+                let subtype = doc
+                    .dereference(annot)
+                    .and_then(|(_, a)| a.as_dict())
+                    .and_then(|a| a.get(b"Subtype"))
+                    .and_then(Object::as_name)
+                    .ok()
+                    .filter(|s| UNSAFE_ANNOTATIONS.contains(s));
+                if let Some(subtype) = subtype {
+                    removed.push(format!("{} annotation", String::from_utf8_lossy(subtype)));
+                }
+                subtype.is_none()
+            })
+            .collect::<Vec<_>>();
+        if kept.len() != count
+            && let Ok(page) = doc.get_dictionary_mut(page_id)
+        {
+            page.set("Annots", kept);
+        }
+    }
+    // the removed annotations go first, so what they held isn't listed twice
+    doc.prune_objects();
+    let referenced = doc
+        .objects
+        .iter()
+        .filter_map(|(id, object)| Some((*id, unsafe_action(object.as_dict().ok()?)?)))
+        .collect::<HashMap<_, _>>();
+    doc.objects
+        .values_mut()
+        .for_each(|object| strip(object, &referenced, &mut removed));
+    // the stripped scripts, files and media are no longer referenced
+    doc.prune_objects();
+    // while the file streams referenced from elsewhere are dropped (leaving null references)
+    let embedded = doc
+        .objects
+        .iter()
+        .filter(|(_, object)| {
+            object
+                .as_stream()
+                .is_ok_and(|s| s.dict.has_type(b"EmbeddedFile"))
+        })
+        .map(|(id, _)| *id)
+        .collect::<Vec<_>>();
+    for id in embedded {
+        doc.objects.remove(&id);
+        removed.push("embedded file stream".into());
+    }
+
+    let mut counts = BTreeMap::new();
+    removed
+        .into_iter()
+        .for_each(|kind| *counts.entry(kind).or_insert(0) += 1);
+    counts
+        .into_iter()
+        .map(|(kind, count)| {
+            format!(
+                "`{}`: {} ({})",
+                file.to_string_lossy().cyan(),
+                kind,
+                count.to_string().cyan()
+            )
+        })
+        .collect()
+}
@@ -0,0 +1,428 @@
+use super::{
+    MergeRunError,
+    page::{self, Anchor, Geometry},
+};
+use cms::{
+    builder::{SignedDataBuilder, SignerInfoBuilder},
+    cert::{CertificateChoices, IssuerAndSerialNumber},
+    signed_data::{EncapsulatedContentInfo, SignerIdentifier},
+};
+use der::{
+    Any, Decode, Encode,
+    asn1::{OctetString, SetOfVec},
+};
+use lopdf::{
+    Document, IncrementalDocument, Object, ObjectId, Stream, StringFormat,
+    content::{Content, Operation},
+    dictionary,
+};
+use p12_keystore::KeyStore;
+use rsa::{
+    RsaPrivateKey,
+    pkcs1v15::{Signature, SigningKey},
+    pkcs8::DecodePrivateKey,
+};
+use sha2::{Digest, Sha256};
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+use x509_cert::{
+    Certificate,
+    attr::Attribute,
+    spki::{AlgorithmIdentifierOwned, ObjectIdentifier},
+};
+
+/// `id-data` content type (the signed content is the PDF itself, detached).
+const ID_DATA: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.7.1");
+/// `id-sha256` digest algorithm.
+const ID_SHA_256: ObjectIdentifier = ObjectIdentifier::new_unwrap("2.16.840.1.101.3.4.2.1");
+/// `id-aa-signingCertificateV2` attribute, required by PAdES baseline signatures.
+const ID_SIGNING_CERTIFICATE_V2: ObjectIdentifier =
+    ObjectIdentifier::new_unwrap("1.2.840.113549.1.9.16.2.47");
+/// Bytes reserved for the CMS signature in `/Contents` (written as hex, so twice as many chars).
+const CONTENTS_SIZE: usize = 16384;
+/// `/ByteRange` values written before the real ones are known (wide enough for any offset).
+const BYTE_RANGE_PLACEHOLDER: i64 = 9_999_999_999;
+/// Signature field name (numbered, since the output can be signed more than once).
+const FIELD_NAME: &str = "PdmersSignature";
+/// Font resource name used by the visible signature.
+const FONT_NAME: &[u8] = b"PdmersSign";
+/// Visible signature font size.
+const FONT_SIZE: f32 = 8.0;
+/// Courier glyph width (all glyphs have the same width, which makes the box width easy to
+/// compute).
+const GLYPH_WIDTH: f32 = 0.6;
+/// Distance between the visible signature text and its border.
+const PADDING: f32 = 4.0;
+/// Distance between the visible signature and the page edges.
+const MARGIN: f32 = 18.0;
+
+/// Output signing options.
+#[derive(Debug, PartialEq)]
+pub struct Signing {
+    /// PKCS#12 file holding the private key and its certificate chain.
+    pub keystore: PathBuf,
+    /// Environment variable holding the keystore passphrase (empty when [`None`]).
+    pub password_env: Option<String>,
+    /// Why the document was signed.
+    pub reason: Option<String>,
+    /// Where the document was signed.
+    pub location: Option<String>,
+    /// Where to draw the signature box (the signature is invisible when [`None`]).
+    pub visible: Option<Anchor>,
+    /// Page (starting at `1`) holding the signature field (the last one when [`None`]).
+    pub page: Option<u32>,
+}
+
+/// Signing key and certificates read from the keystore, ready to sign the saved output.
+pub struct Signer<'a> {
+    options: &'a Signing,
+    key: SigningKey<Sha256>,
+    /// Signer certificate followed by the rest of the chain.
+    chain: Vec<Certificate>,
+    /// Page (starting at `1`) holding the signature field.
+    page: u32,
+}
+
+/// Current UTC time as (`year`, `month`, `day`, `hour`, `minute`, `second`).
+fn utc_now() -> (i64, i64, i64, i64, i64, i64) {
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs() as i64);
+    let (days, rest) = (secs.div_euclid(86400), secs.rem_euclid(86400));
+    // civil date from the days since 1970-01-01 (Howard Hinnant's algorithm)
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day, rest / 3600, rest % 3600 / 60, rest % 60)
+}
+
+/// Common name of the certificate subject (the whole subject when there's none).
+fn common_name(cert: &Certificate) -> String {
+    let subject = cert.tbs_certificate.subject.to_string();
+    subject
+        .split(',')
+        .find_map(|part| part.trim().strip_prefix("CN="))
+        .map_or(subject.clone(), String::from)
+}
+
+/// `signing-certificate-v2` attribute (RFC 5035) binding the signer certificate to the signature.
+///
+/// `ESSCertIDv2` only holds the certificate hash (SHA-256 is the default algorithm), so the
+/// attribute value is a sequence of a sequence of a sequence with an octet string.
+fn signing_certificate(cert: &Certificate) -> Result<Attribute, der::Error> {
+    let hash = OctetString::new(Sha256::digest(cert.to_der()?).to_vec())?;
+    let value = Any::encode_from(&vec![vec![vec![hash]]])?;
+    Ok(Attribute {
+        oid: ID_SIGNING_CERTIFICATE_V2,
+        values: SetOfVec::try_from(vec![value])?,
+    })
+}
+
+/// First numbered signature field name not taken by the existing fields.
+fn field_name(taken: &[String]) -> String {
+    (1..)
+        .map(|n| format!("{FIELD_NAME}{n}"))
+        .find(|name| !taken.contains(name))
+        .unwrap_or_default()
+}
+
+/// Finds the `[0 X X X]` placeholder of `/ByteRange` and the zeroed `/Contents` of the signature
+/// within the incremental update (the bytes from `start`), returning their offsets.
+fn placeholders(bytes: &[u8], start: usize) -> Option<(usize, usize)> {
+    let find = |needle: &[u8]| {
+        bytes[start..]
+            .windows(needle.len())
+            .position(|w| w == needle)
+            .map(|p| p + start)
+    };
+    let range = format!("[0 {0} {0} {0}]", BYTE_RANGE_PLACEHOLDER);
+    let contents = format!("<{}>", "0".repeat(CONTENTS_SIZE * 2));
+    Some((find(range.as_bytes())?, find(contents.as_bytes())?))
+}
+
+impl Signing {
+    /// Reads the private key and the certificates from the keystore and checks the signature
+    /// page against the output `pages`, so nothing gets saved when the output can't be signed.
+    pub fn signer(&self, pages: u32) -> Result<Signer<'_>, MergeRunError> {
+        let page = self.page.unwrap_or(pages);
+        if !(1..=pages).contains(&page) {
+            return Err(MergeRunError::SignaturePageNotFound(page));
+        }
+        let err = || MergeRunError::CouldNotLoadKeystore(self.keystore.clone());
+        let password = match &self.password_env {
+            Some(var) => {
+                env::var(var).map_err(|_| MergeRunError::PasswordEnvIsNotSet(var.into()))?
+            }
+            None => String::new(),
+        };
+        let data = fs::read(&self.keystore).map_err(|_| err())?;
+        let keystore = KeyStore::from_pkcs12(&data, &password).map_err(|_| err())?;
+        let (_, chain) = keystore.private_key_chain().ok_or_else(err)?;
+        let key = RsaPrivateKey::from_pkcs8_der(chain.key()).map_err(|_| err())?;
+        let chain = chain
+            .chain()
+            .iter()
+            .map(|cert| Certificate::from_der(cert.as_der()))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| err())?;
+        match chain.is_empty() {
+            true => Err(err()),
+            false => Ok(Signer {
+                options: self,
+                key: SigningKey::new(key),
+                chain,
+                page,
+            }),
+        }
+    }
+
+    /// Signature appearance (a bordered box listing the signer, date, reason and location) with
+    /// its size.
+    fn appearance(&self, doc: &mut Document, signer: &str, date: &str) -> (ObjectId, f32, f32) {
+        let lines = [
+            Some(format!("Digitally signed by {signer}")),
+            Some(format!("Date: {date}")),
+            self.reason.as_ref().map(|r| format!("Reason: {r}")),
+            self.location.as_ref().map(|l| format!("Location: {l}")),
+        ]
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>();
+        let longest = lines.iter().map(|l| l.chars().count()).max().unwrap_or(0);
+        let width = longest as f32 * GLYPH_WIDTH * FONT_SIZE + 2.0 * PADDING;
+        let height = lines.len() as f32 * FONT_SIZE * 1.25 + 2.0 * PADDING;
+        let mut operations = vec![
+            Operation::new("q", vec![]),
+            Operation::new("w", vec![0.5.into()]),
+            Operation::new("re", vec![0.into(), 0.into(), width.into(), height.into()]),
+            Operation::new("S", vec![]),
+            Operation::new("BT", vec![]),
+            Operation::new("Tf", vec![Object::Name(FONT_NAME.into()), FONT_SIZE.into()]),
+            Operation::new("TL", vec![(FONT_SIZE * 1.25).into()]),
+            Operation::new(
+                "Td",
+                vec![PADDING.into(), (height - PADDING - FONT_SIZE).into()],
+            ),
+        ];
+        for (index, line) in lines.into_iter().enumerate() {
+            if index > 0 {
+                operations.push(Operation::new("T*", vec![]));
+            }
+            operations.push(Operation::new("Tj", vec![Object::string_literal(line)]));
+        }
+        operations.extend([Operation::new("ET", vec![]), Operation::new("Q", vec![])]);
+        let content = Content { operations }.encode().unwrap_or_default();
+        let font = doc.add_object(dictionary! {
+            "Type" => "Font",
+            "Subtype" => "Type1",
+            "BaseFont" => "Courier",
+        });
+        let form = doc.add_object(Stream::new(
+            dictionary! {
+                "Type" => "XObject",
+                "Subtype" => "Form",
+                "BBox" => vec![0.into(), 0.into(), width.into(), height.into()],
+                "Resources" => dictionary! {
+                    "Font" => dictionary! { FONT_NAME => font },
+                },
+            },
+            content,
+        ));
+        (form, width, height)
+    }
+}
+
+impl Signer<'_> {
+    /// Signs the saved output, appending the signature (`ETSI.CAdES.detached`, PAdES baseline
+    /// compatible) as an incremental update, so the saved bytes stay untouched.
+    pub fn sign(&self, output: &Path) -> Result<(), MergeRunError> {
+        let failed = || MergeRunError::CouldNotEditMergedPages("sign".into());
+        let bytes =
+            fs::read(output).map_err(|_| MergeRunError::CouldNotSaveTheOutput(output.into()))?;
+        let prev = Document::load_mem(&bytes).map_err(|_| failed())?;
+        let page_id = *prev.get_pages().get(&self.page).ok_or_else(failed)?;
+        let root_id = prev
+            .trailer
+            .get(b"Root")
+            .and_then(Object::as_reference)
+            .map_err(|_| failed())?;
+        let owned = |object: Option<&Object>| {
+            object
+                .and_then(|o| prev.dereference(o).ok())
+                .map(|(_, o)| o.clone())
+        };
+        let mut acro_form = owned(
+            prev.get_dictionary(root_id)
+                .map_err(|_| failed())?
+                .get(b"AcroForm")
+                .ok(),
+        )
+        .and_then(|f| f.as_dict().ok().cloned())
+        .unwrap_or_default();
+        let mut fields = owned(acro_form.get(b"Fields").ok())
+            .and_then(|f| f.as_array().ok().cloned())
+            .unwrap_or_default();
+        let prev_names = fields
+            .iter()
+            .filter_map(|f| prev.dereference(f).ok())
+            .filter_map(|(_, f)| f.as_dict().ok()?.get(b"T").ok()?.as_str().ok())
+            .map(|t| String::from_utf8_lossy(t).into_owned())
+            .collect::<Vec<_>>();
+        let mut annots = owned(
+            prev.get_dictionary(page_id)
+                .map_err(|_| failed())?
+                .get(b"Annots")
+                .ok(),
+        )
+        .and_then(|a| a.as_array().ok().cloned())
+        .unwrap_or_default();
+        let geometry = Geometry::of(&prev, page_id);
+
+        let mut update = IncrementalDocument::create_from(bytes, prev);
+        let doc = &mut update.new_document;
+        let (year, month, day, hour, minute, second) = utc_now();
+        let placeholder = Object::Integer(BYTE_RANGE_PLACEHOLDER);
+        let mut signature = dictionary! {
+            "Type" => "Sig",
+            "Filter" => "Adobe.PPKLite",
+            "SubFilter" => "ETSI.CAdES.detached",
+            "ByteRange" => vec![0.into(), placeholder.clone(), placeholder.clone(), placeholder],
+            "Contents" => Object::String(vec![0; CONTENTS_SIZE], StringFormat::Hexadecimal),
+            "M" => Object::string_literal(format!(
+                "D:{year:04}{month:02}{day:02}{hour:02}{minute:02}{second:02}Z"
+            )),
+        };
+        if let Some(reason) = &self.options.reason {
+            signature.set("Reason", Object::string_literal(reason.as_str()));
+        }
+        if let Some(location) = &self.options.location {
+            signature.set("Location", Object::string_literal(location.as_str()));
+        }
+        let signature_id = doc.add_object(signature);
+        let mut widget = dictionary! {
+            "Type" => "Annot",
+            "Subtype" => "Widget",
+            "FT" => "Sig",
+            "T" => Object::string_literal(field_name(&prev_names)),
+            "V" => signature_id,
+            "P" => page_id,
+            // printable and locked
+            "F" => 132,
+            "Rect" => vec![0.into(), 0.into(), 0.into(), 0.into()],
+        };
+        if let Some(anchor) = self.options.visible {
+            let date =
+                format!("{year:04}-{month:02}-{day:02} {hour:02}:{minute:02}:{second:02} UTC");
+            let name = common_name(&self.chain[0]);
+            let (form, width, height) = self.options.appearance(doc, &name, &date);
+            // the box is rotated along with the page, so it reads upright as displayed
+            let [a, b, c, d, ..] = geometry.visual_matrix();
+            if let Ok(form) = doc.get_object_mut(form).and_then(Object::as_stream_mut) {
+                form.dict
+                    .set("Matrix", [a, b, c, d, 0.0, 0.0].map(Object::Real).to_vec());
+            }
+            let (x, y) = anchor.place(geometry.visual_size(), (width, height), MARGIN);
+            let rect =
+                page::transform_rect(geometry.visual_matrix(), [x, y, x + width, y + height]);
+            widget.set(
+                "Rect",
+                rect.into_iter().map(Object::Real).collect::<Vec<_>>(),
+            );
+            widget.set("AP", dictionary! { "N" => form });
+        }
+        let widget_id = doc.add_object(widget);
+        fields.push(widget_id.into());
+        annots.push(widget_id.into());
+        acro_form.set("Fields", fields);
+        // signatures exist and the file must only be appended to
+        acro_form.set("SigFlags", 3);
+        update
+            .opt_clone_object_to_new_document(root_id)
+            .map_err(|_| failed())?;
+        update
+            .opt_clone_object_to_new_document(page_id)
+            .map_err(|_| failed())?;
+        let doc = &mut update.new_document;
+        doc.get_dictionary_mut(root_id)
+            .map_err(|_| failed())?
+            .set("AcroForm", acro_form);
+        doc.get_dictionary_mut(page_id)
+            .map_err(|_| failed())?
+            .set("Annots", annots);
+
+        let mut signed = Vec::new();
+        update.save_to(&mut signed).map_err(|_| failed())?;
+        let start = update.get_prev_documents_bytes().len();
+        let (range_at, contents_at) = placeholders(&signed, start).ok_or_else(failed)?;
+        let contents_end = contents_at + CONTENTS_SIZE * 2 + 2;
+        let range = [0, contents_at, contents_end, signed.len() - contents_end];
+        let placeholder_len = format!("[0 {0} {0} {0}]", BYTE_RANGE_PLACEHOLDER).len();
+        let written = format!("[{} {} {} {}]", range[0], range[1], range[2], range[3]);
+        let written = format!("{written:<placeholder_len$}");
+        signed[range_at..range_at + placeholder_len].copy_from_slice(written.as_bytes());
+
+        let mut hasher = Sha256::new();
+        hasher.update(&signed[..contents_at]);
+        hasher.update(&signed[contents_end..]);
+        let digest = hasher.finalize();
+        let cms = self.cms(&digest).map_err(|_| failed())?;
+        if cms.len() > CONTENTS_SIZE {
+            return Err(failed());
+        }
+        let hex = cms.iter().map(|b| format!("{b:02X}")).collect::<String>();
+        signed[contents_at + 1..contents_at + 1 + hex.len()].copy_from_slice(hex.as_bytes());
+        fs::write(output, signed).map_err(|_| MergeRunError::CouldNotSaveTheOutput(output.into()))
+    }
+
+    /// Detached CMS `SignedData` over the document `digest`, embedding the certificate chain.
+    fn cms(&self, digest: &[u8]) -> Result<Vec<u8>, der::Error> {
+        let failed = |_| der::Error::from(der::ErrorKind::Failed);
+        let content = EncapsulatedContentInfo {
+            econtent_type: ID_DATA,
+            econtent: None,
+        };
+        let digest_algorithm = AlgorithmIdentifierOwned {
+            oid: ID_SHA_256,
+            parameters: None,
+        };
+        let cert = &self.chain[0];
+        let id = SignerIdentifier::IssuerAndSerialNumber(IssuerAndSerialNumber {
+            issuer: cert.tbs_certificate.issuer.clone(),
+            serial_number: cert.tbs_certificate.serial_number.clone(),
+        });
+        let mut info = SignerInfoBuilder::new(
+            &self.key,
+            id,
+            digest_algorithm.clone(),
+            &content,
+            Some(digest),
+        )
+        .map_err(failed)?;
+        info.add_signed_attribute(signing_certificate(cert)?)
+            .map_err(failed)?;
+        let mut builder = SignedDataBuilder::new(&content);
+        builder
+            .add_digest_algorithm(digest_algorithm)
+            .map_err(failed)?;
+        for cert in &self.chain {
+            builder
+                .add_certificate(CertificateChoices::Certificate(cert.clone()))
+                .map_err(failed)?;
+        }
+        builder
+            .add_signer_info::<_, Signature>(info)
+            .map_err(failed)?
+            .build()
+            .map_err(failed)?
+            .to_der()
+    }
+}
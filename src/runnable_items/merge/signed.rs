@@ -0,0 +1,157 @@
+use super::{MergeRunError, limits::Limits, provenance};
+use lopdf::{Document, IncrementalDocument, Object, ObjectId};
+use std::{collections::HashSet, path::Path};
+
+/// Whether the field tree (from `fields`) holds a signed signature field.
+fn has_signature(doc: &Document, fields: &[Object], visited: &mut HashSet<ObjectId>) -> bool {
+    fields.iter().any(|field| {
+        if let Object::Reference(id) = field
+            && !visited.insert(*id)
+        {
+            return false;
+        }
+        let Ok((_, Object::Dictionary(field))) = doc.dereference(field) else {
+            return false;
+        };
+        let is_sig = field
+            .get(b"FT")
+            .and_then(Object::as_name)
+            .is_ok_and(|ft| ft == b"Sig");
+        if is_sig && field.has(b"V") {
+            return true;
+        }
+        let kids = field
+            .get(b"Kids")
+            .and_then(|k| doc.dereference(k))
+            .and_then(|(_, k)| k.as_array())
+            .cloned()
+            .unwrap_or_default();
+        has_signature(doc, &kids, visited)
+    })
+}
+
+/// Whether the document is digitally signed: it has a signed signature field or a permissions
+/// dictionary (`/Perms`, used by certification signatures and usage rights).
+pub fn is_signed(doc: &Document) -> bool {
+    let Ok(catalog) = doc.catalog() else {
+        return false;
+    };
+    if catalog.has(b"Perms") {
+        return true;
+    }
+    let fields = catalog
+        .get(b"AcroForm")
+        .and_then(|f| doc.dereference(f))
+        .and_then(|(_, f)| f.as_dict())
+        .and_then(|f| f.get(b"Fields"))
+        .and_then(|f| doc.dereference(f))
+        .and_then(|(_, f)| f.as_array())
+        .cloned()
+        .unwrap_or_default();
+    has_signature(doc, &fields, &mut HashSet::new())
+}
+
+/// Signed input kept byte for byte at the start of the output, so its signature stays valid (the
+/// other inputs are appended to it as an incremental update).
+pub struct SignedBase {
+    bytes: Vec<u8>,
+    doc: Document,
+}
+
+impl SignedBase {
+    /// Reads the signed input at `path` within the resource `limits` (it can't be encrypted,
+    /// since the appended objects would have to be encrypted too).
+    pub fn load(path: &Path, limits: &Limits) -> Result<Self, MergeRunError> {
+        let bytes = limits.read(path)?;
+        let doc = Document::load_mem(&bytes)
+            .map_err(|_| MergeRunError::CouldNotLoadInput(path.into()))?;
+        if doc.is_encrypted() {
+            return Err(MergeRunError::KeptInputIsEncrypted(path.into()));
+        }
+        limits.check(&doc, path)?;
+        Ok(Self { bytes, doc })
+    }
+
+    /// Number of pages of the signed input.
+    pub fn page_count(&self) -> u32 {
+        self.doc.get_pages().len() as u32
+    }
+
+    /// Saves the signed input followed by an incremental update adding the pages of `doc` after
+    /// its own ones (and its provenance marker, if `doc` has one).
+    pub fn append(self, mut doc: Document, output: &Path) -> Result<(), MergeRunError> {
+        let failed = || MergeRunError::CouldNotEditMergedPages("keep-signature-of".into());
+        let root_pages = self
+            .doc
+            .catalog()
+            .and_then(|c| c.get(b"Pages"))
+            .and_then(Object::as_reference)
+            .map_err(|_| MergeRunError::RootPageNotFound)?;
+        let count = self
+            .doc
+            .get_dictionary(root_pages)
+            .and_then(|p| p.get(b"Count"))
+            .and_then(Object::as_i64)
+            .map_err(|_| MergeRunError::RootPageNotFound)?;
+        doc.renumber_objects_with(self.doc.max_id + 1);
+        let catalog_id = doc
+            .trailer
+            .get(b"Root")
+            .and_then(Object::as_reference)
+            .map_err(|_| MergeRunError::CatalogIsNone)?;
+        let pages_id = doc
+            .catalog()
+            .and_then(|c| c.get(b"Pages"))
+            .and_then(Object::as_reference)
+            .map_err(|_| MergeRunError::RootPageNotFound)?;
+        let info_id = doc.trailer.get(b"Info").and_then(Object::as_reference).ok();
+        let pages = doc.page_iter().collect::<Vec<_>>();
+        let max_id = doc.max_id;
+
+        let mut update = IncrementalDocument::create_from(self.bytes, self.doc);
+        update
+            .opt_clone_object_to_new_document(root_pages)
+            .map_err(|_| failed())?;
+        let new = &mut update.new_document;
+        // the merged catalog and page tree root are replaced by the signed input ones
+        for (id, mut object) in doc.objects {
+            if id == catalog_id || id == pages_id || Some(id) == info_id {
+                continue;
+            }
+            if pages.contains(&id)
+                && let Object::Dictionary(page) = &mut object
+            {
+                page.set("Parent", root_pages);
+            }
+            new.objects.insert(id, object);
+        }
+        new.max_id = max_id;
+        let root = new.get_dictionary_mut(root_pages).map_err(|_| failed())?;
+        let mut kids = root
+            .get(b"Kids")
+            .and_then(Object::as_array)
+            .cloned()
+            .unwrap_or_default();
+        kids.extend(pages.iter().copied().map(Object::Reference));
+        root.set("Kids", kids);
+        root.set("Count", count + pages.len() as i64);
+        // the marker goes to the signed input Info dictionary
+        if info_id.is_some() {
+            if let Ok(info) = update
+                .get_prev_documents()
+                .trailer
+                .get(b"Info")
+                .and_then(Object::as_reference)
+            {
+                update
+                    .opt_clone_object_to_new_document(info)
+                    .map_err(|_| failed())?;
+            }
+            provenance::mark(&mut update.new_document);
+        }
+        update
+            .save(output)
+            .map(|_| ())
+            .map_err(|_| MergeRunError::CouldNotSaveTheOutput(output.into()))
+    }
+}
@@ -0,0 +1,121 @@
+use super::{
+    MergeRunError,
+    limits::Limits,
+    load_pdf,
+    page::{self, Geometry},
+};
+use lopdf::{
+    Document, Object,
+    content::{Content, Operation},
+};
+use std::{collections::BTreeSet, path::Path};
+
+/// Where to place the stamp pages.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Layer {
+    /// Under the page content (like a letterhead).
+    Under,
+    /// Over the page content.
+    Over,
+}
+
+impl Layer {
+    /// Step name (used in error reports).
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Under => "underlay",
+            Self::Over => "overlay",
+        }
+    }
+
+    /// XObject resource name of the stamp page at `index`.
+    fn resource(&self, index: usize) -> String {
+        match self {
+            Self::Under => format!("PdmersUnderlay{index}"),
+            Self::Over => format!("PdmersOverlay{index}"),
+        }
+    }
+}
+
+/// Draws the pages of the stamp PDF at `path` on the document pages: the page `N` of the stamp
+/// goes to the page `N` of the document (the last stamp page is repeated when the stamp has less
+/// pages than the document).
+///
+/// The stamp pages are turned into form XObjects scaled to fit the document pages (keeping their
+/// aspect ratio). The stamp is loaded within the inputs resource `limits`.
+pub fn apply(
+    doc: &mut Document,
+    path: &Path,
+    layer: Layer,
+    limits: &Limits,
+) -> Result<(), MergeRunError> {
+    let err = |_| MergeRunError::CouldNotEditMergedPages(layer.name().into());
+    let mut stamp = load_pdf(path, limits)?;
+    stamp.renumber_objects_with(doc.max_id + 1);
+    doc.max_id = doc.max_id.max(stamp.max_id);
+
+    // turn the stamp pages into form XObjects (copying the objects they depend on)
+    let mut forms = Vec::new();
+    for page_id in stamp.page_iter() {
+        let geometry = Geometry::of(&stamp, page_id);
+        let form = page::form_xobject(&stamp, page_id);
+        let mut found = BTreeSet::new();
+        page::references(&stamp, &Object::Stream(form.clone()), &mut found);
+        for id in found {
+            if let Ok(object) = stamp.get_object(id) {
+                doc.objects.insert(id, object.clone());
+            }
+        }
+        forms.push((doc.add_object(form), geometry));
+    }
+    if forms.is_empty() {
+        return Err(MergeRunError::CouldNotLoadInput(path.into()));
+    }
+
+    for (index, page_id) in doc.page_iter().collect::<Vec<_>>().into_iter().enumerate() {
+        let index = index.min(forms.len() - 1);
+        let (form, stamp) = forms[index];
+        let name = layer.resource(index);
+        let geometry = Geometry::of(doc, page_id);
+        let (width, height) = geometry.visual_size();
+        let [x0, y0, x1, y1] = stamp.bbox;
+        let scale = (width / (x1 - x0)).min(height / (y1 - y0));
+        let dx = (width - (x1 - x0) * scale) / 2.0 - x0 * scale;
+        let dy = (height - (y1 - y0) * scale) / 2.0 - y0 * scale;
+        let content = Content {
+            operations: vec![
+                Operation::new("q", vec![]),
+                Operation::new(
+                    "cm",
+                    geometry
+                        .visual_matrix()
+                        .into_iter()
+                        .map(Object::Real)
+                        .collect(),
+                ),
+                Operation::new(
+                    "cm",
+                    vec![
+                        scale.into(),
+                        0.into(),
+                        0.into(),
+                        scale.into(),
+                        dx.into(),
+                        dy.into(),
+                    ],
+                ),
+                Operation::new("Do", vec![Object::Name(name.clone().into_bytes())]),
+                Operation::new("Q", vec![]),
+            ],
+        }
+        .encode()
+        .map_err(err)?;
+        page::add_resource(doc, page_id, b"XObject", name.as_bytes(), form.into()).map_err(err)?;
+        match layer {
+            Layer::Under => page::stamp_under(doc, page_id, content),
+            Layer::Over => page::stamp_over(doc, page_id, content),
+        }
+        .map_err(err)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,78 @@
+use super::identity::{DedupeBy, FileId};
+use colored::Colorize;
+use std::{collections::HashSet, path::Path, str::FromStr};
+
+/// Which symlinks found within the input directories are followed.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum FollowSymlinks {
+    /// No symlink at all.
+    Never,
+    /// Symlinks to files only.
+    Files,
+    /// Symlinks to files and directories (each directory is walked once).
+    #[default]
+    All,
+}
+
+impl FromStr for FollowSymlinks {
+    type Err = ();
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "never" => Ok(Self::Never),
+            "files" => Ok(Self::Files),
+            "all" => Ok(Self::All),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Symlinks handling while walking the input directories: the directories already walked (to
+/// break symlink cycles) and the skipped links, listed in the report.
+pub struct Links {
+    follow: FollowSymlinks,
+    visited: HashSet<FileId>,
+    pub skipped: Vec<String>,
+}
+
+impl Links {
+    pub fn new(follow: FollowSymlinks) -> Self {
+        Self {
+            follow,
+            visited: HashSet::new(),
+            skipped: Vec::new(),
+        }
+    }
+
+    /// Whether the entry at `path` is walked. The `listed` ones (passed as input) are always
+    /// followed and start a new walk, while the ones found within a directory are skipped when
+    /// they're links not followed, broken links or directories already walked.
+    pub fn admits(&mut self, path: &Path, listed: bool) -> bool {
+        if listed {
+            self.visited.clear();
+        } else if path.symlink_metadata().is_ok_and(|m| m.is_symlink()) {
+            let reason = match (self.follow, path.is_dir()) {
+                _ if !path.exists() => Some("broken link"),
+                (FollowSymlinks::Never, _) => Some("link not followed"),
+                (FollowSymlinks::Files, true) => Some("directory link not followed"),
+                _ => None,
+            };
+            if let Some(reason) = reason {
+                self.skip(path, reason);
+                return false;
+            }
+        }
+        if path.is_dir()
+            && let Ok(id) = DedupeBy::Inode.id(path)
+            && !self.visited.insert(id)
+        {
+            self.skip(path, "directory already walked");
+            return false;
+        }
+        true
+    }
+
+    fn skip(&mut self, path: &Path, reason: &str) {
+        self.skipped
+            .push(format!("`{}`: {}", path.to_string_lossy().cyan(), reason));
+    }
+}
@@ -1,18 +1,432 @@
 use super::*;
-use std::path::Path;
+use clap::{Parser, error::ErrorKind};
+use limits::{DEFAULT_MAX_NESTING, Limit, OBJECT_STREAM_CEILING};
+use lopdf::{ObjectId, Permissions, Stream, content::Content, dictionary};
+use std::{env, process};
+
+/// Fresh directory (within the system temp one) holding the fixtures of the `name` test.
+fn fixture_dir(name: &str) -> PathBuf {
+    let dir = env::temp_dir().join(format!("pdmers-{}-{name}", process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).expect("the fixture directory should be created");
+    dir
+}
+
+/// Document with a blank page of each `(width, height)` size. Each page content is labelled
+/// with its number, by a `/PN MP` marked point (which doesn't draw anything).
+fn document(sizes: &[(i64, i64)]) -> Document {
+    let mut doc = Document::with_version("1.7");
+    let pages_id = doc.new_object_id();
+    let kids = sizes
+        .iter()
+        .enumerate()
+        .map(|(index, (width, height))| {
+            let label = format!("/P{} MP", index + 1).into_bytes();
+            let content = doc.add_object(Stream::new(dictionary! {}, label));
+            doc.add_object(dictionary! {
+                "Type" => "Page",
+                "Parent" => pages_id,
+                "MediaBox" => vec![0.into(), 0.into(), (*width).into(), (*height).into()],
+                "Contents" => content,
+            })
+            .into()
+        })
+        .collect::<Vec<Object>>();
+    doc.objects.insert(
+        pages_id,
+        Object::Dictionary(dictionary! {
+            "Type" => "Pages",
+            "Count" => kids.len() as i64,
+            "Kids" => kids,
+        }),
+    );
+    let catalog = doc.add_object(dictionary! { "Type" => "Catalog", "Pages" => pages_id });
+    doc.trailer.set("Root", catalog);
+    doc
+}
+
+/// Box (`MediaBox`, `TrimBox`, ...) of the page, if it has one.
+fn page_box(doc: &Document, page_id: ObjectId, key: &[u8]) -> Option<Vec<f32>> {
+    let values = doc
+        .get_dictionary(page_id)
+        .ok()?
+        .get(key)
+        .ok()?
+        .as_array()
+        .ok()?;
+    values.iter().map(|v| v.as_float().ok()).collect()
+}
+
+/// Matrices (`cm` operands) the `q` blocks of the page content open with, which place the
+/// pages drawn on it.
+fn placements(doc: &Document, page_id: ObjectId) -> Vec<Vec<f32>> {
+    let content = Content::decode(&doc.get_page_content(page_id).unwrap()).unwrap();
+    content
+        .operations
+        .windows(2)
+        .filter(|ops| ops[0].operator == "q" && ops[1].operator == "cm")
+        .map(|ops| {
+            ops[1]
+                .operands
+                .iter()
+                .map(|o| o.as_float().unwrap())
+                .collect()
+        })
+        .collect()
+}
+
+/// Labels (see [`document`]) of the pages the page content draws, in order.
+fn drawn(doc: &Document, page_id: ObjectId) -> Vec<String> {
+    let (resources, _) = doc.get_page_resources(page_id).unwrap();
+    let xobjects = resources
+        .unwrap()
+        .get(b"XObject")
+        .unwrap()
+        .as_dict()
+        .unwrap();
+    let content = Content::decode(&doc.get_page_content(page_id).unwrap()).unwrap();
+    content
+        .operations
+        .iter()
+        .filter(|op| op.operator == "Do")
+        .map(|op| {
+            let name = op.operands[0].as_name().unwrap();
+            let id = xobjects.get(name).unwrap().as_reference().unwrap();
+            let form = doc.get_object(id).unwrap().as_stream().unwrap();
+            let bytes = form.decompressed_content().unwrap_or(form.content.clone());
+            let content = Content::decode(&bytes).unwrap();
+            let label = content
+                .operations
+                .iter()
+                .find(|op| op.operator == "MP")
+                .unwrap();
+            String::from_utf8_lossy(label.operands[0].as_name().unwrap()).into_owned()
+        })
+        .collect()
+}
+
+/// Labels (see [`document`]) of the document pages, in order.
+fn labels(doc: &Document) -> Vec<String> {
+    doc.page_iter()
+        .map(|page_id| {
+            let content = Content::decode(&doc.get_page_content(page_id).unwrap()).unwrap();
+            let label = content
+                .operations
+                .iter()
+                .find(|op| op.operator == "MP")
+                .unwrap();
+            String::from_utf8_lossy(label.operands[0].as_name().unwrap()).into_owned()
+        })
+        .collect()
+}
+
+/// Whether the values are equal up to rounding.
+fn approx(values: &[f32], expected: &[f32]) -> bool {
+    values.len() == expected.len()
+        && values
+            .iter()
+            .zip(expected)
+            .all(|(v, e)| (v - e).abs() < 0.01)
+}
+
+/// Runs a merge with the `args` (the binary name left out).
+fn merge(args: &[&str]) -> Result<RunSuccess, MergeRunError> {
+    let args = MergeArgs::try_parse_from([&["merge"], args].concat())
+        .expect("the arguments should be parsed");
+    Merge::try_from(args)
+        .expect("the arguments should be valid")
+        .run_item()
+}
 
 const NO_INPUT: [&str; 3] = ["merge", "-o", "output.pdf"];
 const NO_OUTPUT: [&str; 3] = ["merge", "-i", "input.pdf"];
 const UNPARSEABLE_DEPTH: [&str; 7] = ["merge", "-i", "inputs", "-o", "some.pdf", "-d", "0"];
+const UNPARSEABLE_ORIENTATION: [&str; 8] = [
+    "merge", "-i", "a.pdf", "b.pdf", "-o", "o.pdf", "--orient", "sideways",
+];
+const NEGATIVE_BLEED: [&str; 8] = [
+    "merge",
+    "-i",
+    "a.pdf",
+    "b.pdf",
+    "-o",
+    "o.pdf",
+    "--print-marks",
+    "--bleed=-3",
+];
+const BLANK_SENSITIVITY_OUT_OF_RANGE: [&str; 9] = [
+    "merge",
+    "-i",
+    "a.pdf",
+    "b.pdf",
+    "-o",
+    "o.pdf",
+    "--drop-blank",
+    "--blank-sensitivity",
+    "2",
+];
+const NON_ASCII_BATES_PREFIX: [&str; 8] = [
+    "merge", "-i", "a.pdf", "b.pdf", "-o", "o.pdf", "--bates", "ÀCME",
+];
+const UNPARSEABLE_DEDUPE_MODE: [&str; 8] = [
+    "merge",
+    "-i",
+    "a.pdf",
+    "b.pdf",
+    "-o",
+    "o.pdf",
+    "--dedupe-by",
+    "name",
+];
+const UNPARSEABLE_SYMLINK_POLICY: [&str; 9] = [
+    "merge",
+    "-i",
+    "inputs",
+    "-o",
+    "o.pdf",
+    "-d",
+    "*",
+    "--follow-symlinks",
+    "dirs",
+];
+const UNPARSEABLE_PASSWORD_ENV: [&str; 6] =
+    ["merge", "-i", "a.pdf", "b.pdf#password-env=", "-o", "o.pdf"];
+const PLAIN_USER_PASSWORD: [&str; 9] = [
+    "merge",
+    "-i",
+    "a.pdf",
+    "b.pdf",
+    "-o",
+    "o.pdf",
+    "--encrypt",
+    "--user-password",
+    "hunter2",
+];
+const UNPARSEABLE_BATES_START: [&str; 9] = [
+    "merge",
+    "-i",
+    "a.pdf",
+    "b.pdf",
+    "-o",
+    "o.pdf",
+    "--bates",
+    "X",
+    "--bates-start=-1",
+];
+const WATERMARK_OPACITY_OUT_OF_RANGE: [&str; 10] = [
+    "merge",
+    "-i",
+    "a.pdf",
+    "b.pdf",
+    "-o",
+    "o.pdf",
+    "--watermark-text",
+    "DRAFT",
+    "--watermark-opacity",
+    "1.5",
+];
+const UNPARSEABLE_WATERMARK_PAGES: [&str; 10] = [
+    "merge",
+    "-i",
+    "a.pdf",
+    "b.pdf",
+    "-o",
+    "o.pdf",
+    "--watermark-text",
+    "DRAFT",
+    "--watermark-pages",
+    "3-1",
+];
+const UNPARSEABLE_NUP_GRID: [&str; 8] = [
+    "merge", "-i", "a.pdf", "b.pdf", "-o", "o.pdf", "--nup", "2by2",
+];
+const NUP_GUTTER_OUT_OF_RANGE: [&str; 10] = [
+    "merge",
+    "-i",
+    "a.pdf",
+    "b.pdf",
+    "-o",
+    "o.pdf",
+    "--nup",
+    "2x2",
+    "--nup-gutter",
+    "600",
+];
+const ZERO_BOOKLET_SIGNATURE: [&str; 10] = [
+    "merge",
+    "-i",
+    "a.pdf",
+    "b.pdf",
+    "-o",
+    "o.pdf",
+    "--booklet",
+    "--booklet-signature",
+    "0",
+    "--booklet-creep=0.5",
+];
+const BOOKLET_GUTTER_OUT_OF_RANGE: [&str; 9] = [
+    "merge",
+    "-i",
+    "a.pdf",
+    "b.pdf",
+    "-o",
+    "o.pdf",
+    "--booklet",
+    "--booklet-gutter",
+    "900",
+];
+const BOOKLET_CREEP_OUT_OF_RANGE: [&str; 13] = [
+    "merge",
+    "-i",
+    "a.pdf",
+    "b.pdf",
+    "-o",
+    "o.pdf",
+    "--booklet",
+    "--booklet-signature",
+    "4",
+    "--booklet-gutter",
+    "10",
+    "--booklet-creep",
+    "2",
+];
+const TILE_MARGIN_OUT_OF_RANGE: [&str; 10] = [
+    "merge",
+    "-i",
+    "a.pdf",
+    "b.pdf",
+    "-o",
+    "o.pdf",
+    "--tile",
+    "a4",
+    "--tile-margin",
+    "400",
+];
+const UNKNOWN_PAGE_SCALE: [&str; 10] = [
+    "merge",
+    "-i",
+    "a.pdf",
+    "b.pdf",
+    "-o",
+    "o.pdf",
+    "--page-size",
+    "letter",
+    "--page-scale",
+    "stretch",
+];
+const INVERTED_CROP_BOX: [&str; 8] = [
+    "merge",
+    "-i",
+    "a.pdf",
+    "b.pdf",
+    "-o",
+    "o.pdf",
+    "--crop",
+    "box:500,0,100,700",
+];
+const NESTING_OUT_OF_RANGE: [&str; 8] = [
+    "merge",
+    "-i",
+    "a.pdf",
+    "b.pdf",
+    "-o",
+    "o.pdf",
+    "--max-nesting",
+    "1000",
+];
+const UNKNOWN_BATES_POSITION: [&str; 10] = [
+    "merge",
+    "-i",
+    "a.pdf",
+    "b.pdf",
+    "-o",
+    "o.pdf",
+    "--bates",
+    "X",
+    "--bates-position",
+    "middle",
+];
 
-const SINGLE_FILE_INPUT: [&str; 5] = ["merge", "-i", "f.pdf", "-o", "out.pdf"];
 const OUTPUT_IS_DIR: [&str; 6] = ["merge", "-i", "file.pdf", "other.pdf", "-o", "src"];
 const INPUT_DIRECTORY_REFERENCE: [&str; 6] =
     ["merge", "-i", "somedir/../this.pdf", "dir", "-o", "out.pdf"];
 const TOML_INPUT: [&str; 6] = ["merge", "-i", "pdf.pdf", "Cargo.toml", "-o", "out.pdf"];
 const INPUT_REPETITION: [&str; 8] = ["merge", "-i", "src", "src", "-o", "out.pdf", "-d", "*"];
-const ALREADY_EXISTING_OUTPUT: [&str; 7] = ["merge", "-i", "src", "-o", "f.pdf", "-d", "*"];
 const PARENT_OUTPUT: [&str; 7] = ["merge", "-i", "src", "-o", "some/f.pdf", "-d", "5"];
+const NON_IMAGE_WATERMARK: [&str; 9] = [
+    "merge",
+    "-i",
+    "src",
+    "-o",
+    "out.pdf",
+    "-d",
+    "*",
+    "--watermark-image",
+    "Cargo.toml",
+];
+const NON_PDF_UNDERLAY: [&str; 9] = [
+    "merge",
+    "-i",
+    "src",
+    "-o",
+    "out.pdf",
+    "-d",
+    "*",
+    "--underlay",
+    "Cargo.toml",
+];
+const MISSING_ORDER_FILE: [&str; 9] = [
+    "merge",
+    "-i",
+    "src",
+    "-o",
+    "out.pdf",
+    "-d",
+    "*",
+    "--order",
+    "pages.txt",
+];
+const MISSING_PASSWORD_FILE: [&str; 9] = [
+    "merge",
+    "-i",
+    "src",
+    "-o",
+    "out.pdf",
+    "-d",
+    "*",
+    "--password-file",
+    "passwords.txt",
+];
+const MISSING_KEYSTORE: [&str; 9] = [
+    "merge", "-i", "src", "-o", "out.pdf", "-d", "*", "--sign", "cert.p12",
+];
+const ROOT_IS_FILE: [&str; 9] = [
+    "merge",
+    "-i",
+    "src",
+    "-o",
+    "out.pdf",
+    "-d",
+    "*",
+    "--root",
+    "Cargo.toml",
+];
+const OUTPUT_OUTSIDE_ROOT: [&str; 9] = [
+    "merge", "-i", "src", "-o", "out.pdf", "-d", "*", "--root", "src",
+];
+const NON_CSV_BATES_LOG: [&str; 11] = [
+    "merge",
+    "-i",
+    "src",
+    "-o",
+    "out.pdf",
+    "-d",
+    "*",
+    "--bates",
+    "X",
+    "--bates-log",
+    "log.txt",
+];
 
 #[test]
 fn merge_try_from() {
@@ -29,6 +443,90 @@ fn merge_try_from() {
             MergeArgs::from_iter(UNPARSEABLE_DEPTH),
             MergeBuildError::UnparseableDepth("0".into()),
         ),
+        (
+            MergeArgs::from_iter(UNPARSEABLE_ORIENTATION),
+            MergeBuildError::UnparseableOrientation("sideways".into()),
+        ),
+        (
+            MergeArgs::from_iter(NEGATIVE_BLEED),
+            MergeBuildError::UnparseableBleed("-3".into()),
+        ),
+        (
+            MergeArgs::from_iter(BLANK_SENSITIVITY_OUT_OF_RANGE),
+            MergeBuildError::UnparseableBlankSensitivity("2".into()),
+        ),
+        (
+            MergeArgs::from_iter(NON_ASCII_BATES_PREFIX),
+            MergeBuildError::UnparseableBatesOption("--bates".into(), "ÀCME".into()),
+        ),
+        (
+            MergeArgs::from_iter(UNPARSEABLE_DEDUPE_MODE),
+            MergeBuildError::UnparseableDedupeMode("name".into()),
+        ),
+        (
+            MergeArgs::from_iter(UNPARSEABLE_SYMLINK_POLICY),
+            MergeBuildError::UnparseableSymlinkPolicy("dirs".into()),
+        ),
+        (
+            MergeArgs::from_iter(UNPARSEABLE_PASSWORD_ENV),
+            MergeBuildError::UnparseablePasswordEnv("b.pdf#password-env=".into()),
+        ),
+        (
+            MergeArgs::from_iter(PLAIN_USER_PASSWORD),
+            MergeBuildError::UnparseableEncryptOption("--user-password".into(), "hunter2".into()),
+        ),
+        (
+            MergeArgs::from_iter(UNPARSEABLE_BATES_START),
+            MergeBuildError::UnparseableBatesOption("--bates-start".into(), "-1".into()),
+        ),
+        (
+            MergeArgs::from_iter(UNKNOWN_BATES_POSITION),
+            MergeBuildError::UnparseableBatesOption("--bates-position".into(), "middle".into()),
+        ),
+        (
+            MergeArgs::from_iter(WATERMARK_OPACITY_OUT_OF_RANGE),
+            MergeBuildError::UnparseableWatermarkOption("--watermark-opacity".into(), "1.5".into()),
+        ),
+        (
+            MergeArgs::from_iter(UNPARSEABLE_WATERMARK_PAGES),
+            MergeBuildError::UnparseableWatermarkOption("--watermark-pages".into(), "3-1".into()),
+        ),
+        (
+            MergeArgs::from_iter(UNPARSEABLE_NUP_GRID),
+            MergeBuildError::UnparseableNUpOption("--nup".into(), "2by2".into()),
+        ),
+        (
+            MergeArgs::from_iter(NUP_GUTTER_OUT_OF_RANGE),
+            MergeBuildError::UnparseableNUpOption("--nup-gutter".into(), "600".into()),
+        ),
+        (
+            MergeArgs::from_iter(ZERO_BOOKLET_SIGNATURE),
+            MergeBuildError::UnparseableBookletOption("--booklet-signature".into(), "0".into()),
+        ),
+        (
+            MergeArgs::from_iter(BOOKLET_GUTTER_OUT_OF_RANGE),
+            MergeBuildError::UnparseableBookletOption("--booklet-gutter".into(), "900".into()),
+        ),
+        (
+            MergeArgs::from_iter(BOOKLET_CREEP_OUT_OF_RANGE),
+            MergeBuildError::UnparseableBookletOption("--booklet-creep".into(), "2".into()),
+        ),
+        (
+            MergeArgs::from_iter(TILE_MARGIN_OUT_OF_RANGE),
+            MergeBuildError::UnparseableTileOption("--tile-margin".into(), "400".into()),
+        ),
+        (
+            MergeArgs::from_iter(UNKNOWN_PAGE_SCALE),
+            MergeBuildError::UnparseablePageSizeOption("--page-scale".into(), "stretch".into()),
+        ),
+        (
+            MergeArgs::from_iter(INVERTED_CROP_BOX),
+            MergeBuildError::UnparseablePageBoxOption("--crop".into(), "box:500,0,100,700".into()),
+        ),
+        (
+            MergeArgs::from_iter(NESTING_OUT_OF_RANGE),
+            MergeBuildError::UnparseableLimitOption("--max-nesting".into(), "1000".into()),
+        ),
     ]
     .into_iter()
     .for_each(|(m, err_variant)| assert!(Merge::try_from(m).is_err_and(|e| e == err_variant)));
@@ -36,19 +534,28 @@ fn merge_try_from() {
 
 #[test]
 fn merge_check() {
-    match Path::new("f.pdf").try_exists() {
-        Ok(false) => {
-            panic!(
-                "this test requires a `f.pdf` at crate root. create it and try again...\n(can be empty)"
-            )
-        }
-        Err(_) => panic!("failed to check the `f.pdf` file. This is an unexpected behavior"),
-        _ => {}
-    }
+    // an existing file (can be empty)
+    let f = fixture_dir("check").join("f.pdf");
+    fs::write(&f, b"").expect("the fixture file should be written");
+    let f = f.to_str().expect("the temp dir path should be UTF-8");
+    let single_file_input = ["merge", "-i", f, "-o", "out.pdf"];
+    let already_existing_output = ["merge", "-i", "src", "-o", f, "-d", "*"];
+    let kept_input_not_first = [
+        "merge",
+        "-i",
+        f,
+        "src",
+        "-o",
+        "out.pdf",
+        "-d",
+        "*",
+        "--keep-signature-of",
+        "signed.pdf",
+    ];
     [
         (
-            MergeArgs::from_iter(SINGLE_FILE_INPUT),
-            MergeCheckError::InputIsSingleFile("f.pdf".into()),
+            MergeArgs::from_iter(single_file_input),
+            MergeCheckError::InputIsSingleFile(f.into()),
         ),
         (
             MergeArgs::from_iter(OUTPUT_IS_DIR),
@@ -64,16 +571,52 @@ fn merge_check() {
         ),
         (
             MergeArgs::from_iter(INPUT_REPETITION),
-            MergeCheckError::InputRepetitionWithoutFlag("src".into()),
+            MergeCheckError::InputRepetitionWithoutFlag("src".into(), "src".into()),
         ),
         (
-            MergeArgs::from_iter(ALREADY_EXISTING_OUTPUT),
-            MergeCheckError::OutputAlreadyExists("f.pdf".into()),
+            MergeArgs::from_iter(already_existing_output),
+            MergeCheckError::OutputAlreadyExists(f.into()),
         ),
         (
             MergeArgs::from_iter(PARENT_OUTPUT),
             MergeCheckError::ParentOutputWithoutFlag("some/f.pdf".into()),
         ),
+        (
+            MergeArgs::from_iter(NON_CSV_BATES_LOG),
+            MergeCheckError::BatesLogIsNotCsvFile("log.txt".into()),
+        ),
+        (
+            MergeArgs::from_iter(NON_IMAGE_WATERMARK),
+            MergeCheckError::WatermarkImageIsNotSupported("Cargo.toml".into()),
+        ),
+        (
+            MergeArgs::from_iter(NON_PDF_UNDERLAY),
+            MergeCheckError::StampIsNotPdfFile("Cargo.toml".into()),
+        ),
+        (
+            MergeArgs::from_iter(MISSING_ORDER_FILE),
+            MergeCheckError::OrderIsNotFile("pages.txt".into()),
+        ),
+        (
+            MergeArgs::from_iter(MISSING_PASSWORD_FILE),
+            MergeCheckError::PasswordFileIsNotFile("passwords.txt".into()),
+        ),
+        (
+            MergeArgs::from_iter(MISSING_KEYSTORE),
+            MergeCheckError::KeystoreIsNotFile("cert.p12".into()),
+        ),
+        (
+            MergeArgs::from_iter(kept_input_not_first),
+            MergeCheckError::KeptInputIsNotFirst("signed.pdf".into()),
+        ),
+        (
+            MergeArgs::from_iter(ROOT_IS_FILE),
+            MergeCheckError::RootIsNotDirectory("Cargo.toml".into()),
+        ),
+        (
+            MergeArgs::from_iter(OUTPUT_OUTSIDE_ROOT),
+            MergeCheckError::PathIsOutsideRoot("out.pdf".into()),
+        ),
     ]
     .into_iter()
     .for_each(|(m, err)| {
@@ -82,3 +625,1077 @@ fn merge_check() {
         assert_eq!(m.check_item(), Err(err))
     });
 }
+
+#[test]
+fn dedupe_filled_forms() {
+    let mut doc = Document::with_version("1.5");
+    let content = doc.add_object(Stream::new(dictionary! {}, b"0 0 100 100 re S".to_vec()));
+    let appearance = doc.add_object(Stream::new(
+        dictionary! { "Type" => "XObject", "Subtype" => "Form", "BBox" => vec![0.into(), 0.into(), 100.into(), 20.into()] },
+        Vec::new(),
+    ));
+    let pages_id = doc.new_object_id();
+    // same template (content and widget appearance), filled with different values
+    let page_ids = ["Alice", "Bob", "Alice"].map(|value| {
+        let page_id = doc.new_object_id();
+        let field = doc.add_object(dictionary! {
+            "FT" => "Tx",
+            "T" => Object::string_literal("name"),
+            "V" => Object::string_literal(value),
+        });
+        let widget = doc.add_object(dictionary! {
+            "Type" => "Annot",
+            "Subtype" => "Widget",
+            "Parent" => field,
+            "P" => page_id,
+            "Rect" => vec![0.into(), 0.into(), 100.into(), 20.into()],
+            "AP" => dictionary! { "N" => appearance },
+        });
+        doc.objects.insert(
+            page_id,
+            Object::Dictionary(dictionary! {
+                "Type" => "Page",
+                "Parent" => pages_id,
+                "MediaBox" => vec![0.into(), 0.into(), 100.into(), 100.into()],
+                "Contents" => content,
+                "Annots" => vec![widget.into()],
+            }),
+        );
+        page_id
+    });
+    doc.objects.insert(
+        pages_id,
+        Object::Dictionary(dictionary! {
+            "Type" => "Pages",
+            "Kids" => page_ids.map(Object::Reference).to_vec(),
+            "Count" => 3,
+        }),
+    );
+    let mut hasher = PageHasher::new(&doc);
+    let [alice, bob, alice_again] = page_ids.map(|id| hasher.page(id));
+    assert_ne!(alice, bob);
+    assert_eq!(alice, alice_again);
+}
+
+#[test]
+fn sanitize_page_associated_files() {
+    let mut doc = Document::with_version("1.7");
+    let file = doc.add_object(Stream::new(
+        dictionary! { "Type" => "EmbeddedFile" },
+        b"payload".to_vec(),
+    ));
+    let spec = doc.add_object(dictionary! {
+        "Type" => "Filespec",
+        "F" => Object::string_literal("payload.txt"),
+        "EF" => dictionary! { "F" => file },
+    });
+    let pages_id = doc.new_object_id();
+    let page_id = doc.add_object(dictionary! {
+        "Type" => "Page",
+        "Parent" => pages_id,
+        "MediaBox" => vec![0.into(), 0.into(), 100.into(), 100.into()],
+        "AF" => vec![spec.into()],
+    });
+    doc.objects.insert(
+        pages_id,
+        Object::Dictionary(dictionary! {
+            "Type" => "Pages",
+            "Kids" => vec![page_id.into()],
+            "Count" => 1,
+        }),
+    );
+    let catalog = doc.add_object(dictionary! { "Type" => "Catalog", "Pages" => pages_id });
+    doc.trailer.set("Root", catalog);
+    assert!(!sanitize::apply(&mut doc, Path::new("a.pdf")).is_empty());
+    assert!(!doc.get_dictionary(page_id).unwrap().has(b"AF"));
+    assert!(!doc.objects.contains_key(&file));
+}
+
+#[test]
+fn keep_signature_conflicts() {
+    let base = ["merge", "-i", "a.pdf", "b.pdf", "-o", "o.pdf"];
+    let base = [base.as_slice(), &["--keep-signature-of", "a.pdf"]].concat();
+    [
+        ["--order", "order.txt"].as_slice(),
+        &["--reverse"],
+        &["--nup", "2x2"],
+        &["--booklet"],
+        &["--tile", "a4"],
+        &["--watermark-text", "DRAFT"],
+        &["--underlay", "u.pdf"],
+        &["--page-size", "a4"],
+        &["--orient", "portrait"],
+        &["--crop", "10"],
+        &["--add-margin", "10"],
+        &["--print-marks"],
+        &["--dedupe-pages"],
+        &["--flatten"],
+        &["--sanitize"],
+        &["--anonymize"],
+        &["--encrypt"],
+    ]
+    .into_iter()
+    .for_each(|flag| {
+        let err = MergeArgs::try_parse_from([base.as_slice(), flag].concat()).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::ArgumentConflict, "{flag:?}");
+    });
+    assert!(MergeArgs::try_parse_from([base.as_slice(), &["--drop-blank"]].concat()).is_ok());
+}
+
+/// Input holding a single compressed object stream, in which `depth` arrays are nested and
+/// `padding` spaces follow.
+fn object_stream_input(dir: &Path, depth: usize, padding: usize) -> PathBuf {
+    use flate2::{Compression, write::ZlibEncoder};
+    use std::io::Write;
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::best());
+    let object = format!("{}{}", "[".repeat(depth), "]".repeat(depth));
+    write!(encoder, "2 0 {object}").unwrap();
+    for _ in 0..padding / 8192 {
+        encoder.write_all(&[b' '; 8192]).unwrap();
+    }
+    let data = encoder.finish().unwrap();
+    let mut bytes = format!(
+        "%PDF-1.5\n1 0 obj\n<< /Type /ObjStm /N 1 /First 4 /Filter /FlateDecode /Length {} >>\nstream\n",
+        data.len()
+    )
+    .into_bytes();
+    bytes.extend(data);
+    bytes.extend(b"\nendstream\nendobj\n");
+    let path = dir.join(format!("{depth}-{padding}.pdf"));
+    fs::write(&path, bytes).unwrap();
+    path
+}
+
+#[test]
+fn limits_object_stream_nesting() {
+    let dir = fixture_dir("object-stream-nesting");
+    let exceeded = |path: &Path| match Limits::NONE.read(path) {
+        Err(MergeRunError::InputExceedsLimit(_, limit)) => Some(limit),
+        _ => None,
+    };
+    let deep = object_stream_input(&dir, 1000, 0);
+    assert_eq!(exceeded(&deep), Some(Limit::Nesting(DEFAULT_MAX_NESTING)));
+    // the bomb is rejected at the ceiling, before its nesting is ever reached
+    let bomb = object_stream_input(&dir, 1000, OBJECT_STREAM_CEILING as usize + 8192);
+    assert!(fs::metadata(&bomb).unwrap().len() < 1024 * 1024);
+    assert_eq!(
+        exceeded(&bomb),
+        Some(Limit::StreamBytes(OBJECT_STREAM_CEILING))
+    );
+    let shallow = object_stream_input(&dir, 10, 0);
+    assert!(Limits::NONE.read(&shallow).is_ok());
+}
+
+/// Seeded (SplitMix64) generator for the throwaway signing key, which doesn't have to be secret.
+struct SeededRng(u64);
+
+impl rsa::rand_core::RngCore for SeededRng {
+    fn next_u32(&mut self) -> u32 {
+        self.next_u64() as u32
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E3779B97F4A7C15);
+        let z = (self.0 ^ (self.0 >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+        let z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+        z ^ (z >> 31)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        dest.chunks_mut(8)
+            .for_each(|chunk| chunk.copy_from_slice(&self.next_u64().to_le_bytes()[..chunk.len()]));
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rsa::rand_core::Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+impl rsa::rand_core::CryptoRng for SeededRng {}
+
+/// Throwaway PKCS#12 keystore (empty passphrase) holding a self-signed certificate.
+fn throwaway_keystore() -> Vec<u8> {
+    use der::Encode;
+    use p12_keystore::{Certificate, KeyStore, KeyStoreEntry, PrivateKeyChain};
+    use rsa::{RsaPrivateKey, pkcs1v15::SigningKey, pkcs8::EncodePrivateKey};
+    use std::{str::FromStr, time::Duration};
+    use x509_cert::{
+        builder::{Builder, CertificateBuilder, Profile},
+        name::Name,
+        serial_number::SerialNumber,
+        spki::SubjectPublicKeyInfoOwned,
+        time::Validity,
+    };
+    let key = RsaPrivateKey::new(&mut SeededRng(42), 1024).unwrap();
+    let signer = SigningKey::<sha2::Sha256>::new(key.clone());
+    let cert = CertificateBuilder::new(
+        Profile::Root,
+        SerialNumber::from(1u32),
+        Validity::from_now(Duration::from_secs(3600)).unwrap(),
+        Name::from_str("CN=pdmers test").unwrap(),
+        SubjectPublicKeyInfoOwned::from_key(key.to_public_key()).unwrap(),
+        &signer,
+    )
+    .unwrap()
+    .build::<rsa::pkcs1v15::Signature>()
+    .unwrap();
+    let chain = PrivateKeyChain::new(
+        key.to_pkcs8_der().unwrap().as_bytes(),
+        [1],
+        [Certificate::from_der(&cert.to_der().unwrap()).unwrap()],
+    );
+    let mut keystore = KeyStore::new();
+    keystore.add_entry("pdmers", KeyStoreEntry::PrivateKeyChain(chain));
+    keystore.writer("").write().unwrap()
+}
+
+#[test]
+fn sign_covers_the_whole_output() {
+    use cms::{content_info::ContentInfo, signed_data::SignedData};
+    use der::{Decode, SliceReader, asn1::OctetString, oid::db::rfc5911::ID_MESSAGE_DIGEST};
+    use sha2::{Digest, Sha256};
+    let dir = fixture_dir("sign");
+    let keystore = dir.join("throwaway.p12");
+    fs::write(&keystore, throwaway_keystore()).unwrap();
+    let output = dir.join("out.pdf");
+    let mut doc = Document::with_version("1.7");
+    let pages_id = doc.new_object_id();
+    let page_id = doc.add_object(dictionary! {
+        "Type" => "Page",
+        "Parent" => pages_id,
+        "MediaBox" => vec![0.into(), 0.into(), 100.into(), 100.into()],
+    });
+    doc.objects.insert(
+        pages_id,
+        Object::Dictionary(dictionary! {
+            "Type" => "Pages",
+            "Kids" => vec![page_id.into()],
+            "Count" => 1,
+        }),
+    );
+    let catalog = doc.add_object(dictionary! { "Type" => "Catalog", "Pages" => pages_id });
+    doc.trailer.set("Root", catalog);
+    doc.save(&output).unwrap();
+    let signing = sign::Signing {
+        keystore,
+        password_env: None,
+        reason: None,
+        location: None,
+        visible: None,
+        page: None,
+    };
+    signing.signer(1).unwrap().sign(&output).unwrap();
+
+    let signed = fs::read(&output).unwrap();
+    let doc = Document::load_mem(&signed).unwrap();
+    let signature = doc
+        .objects
+        .values()
+        .filter_map(|o| o.as_dict().ok())
+        .find(|d| d.has_type(b"Sig"))
+        .unwrap();
+    let range = signature
+        .get(b"ByteRange")
+        .and_then(Object::as_array)
+        .unwrap()
+        .iter()
+        .map(|n| n.as_i64().unwrap() as usize)
+        .collect::<Vec<_>>();
+    // everything but the `/Contents` hex string (delimiters included) is covered
+    assert_eq!(range[0], 0);
+    assert_eq!(range[2] + range[3], signed.len());
+    assert_eq!(signed[range[1]], b'<');
+    assert_eq!(signed[range[2] - 1], b'>');
+    assert!(
+        signed[range[1] + 1..range[2] - 1]
+            .iter()
+            .all(u8::is_ascii_hexdigit)
+    );
+    let digest = Sha256::new()
+        .chain_update(&signed[..range[1]])
+        .chain_update(&signed[range[2]..])
+        .finalize();
+
+    let contents = signature.get(b"Contents").and_then(Object::as_str).unwrap();
+    let info = ContentInfo::decode(&mut SliceReader::new(contents).unwrap()).unwrap();
+    let signed_data = info.content.decode_as::<SignedData>().unwrap();
+    let attributes = signed_data
+        .signer_infos
+        .0
+        .get(0)
+        .unwrap()
+        .signed_attrs
+        .clone()
+        .unwrap();
+    let message_digest = attributes
+        .iter()
+        .find(|a| a.oid == ID_MESSAGE_DIGEST)
+        .and_then(|a| a.values.get(0))
+        .and_then(|v| v.decode_as::<OctetString>().ok())
+        .unwrap();
+    assert_eq!(message_digest.as_bytes(), digest.as_slice());
+}
+
+#[test]
+fn password_unlock_order() {
+    let dir = fixture_dir("unlock-order");
+    let user = dir.join("user.txt");
+    fs::write(&user, "right").unwrap();
+    let mut doc = document(&[(100, 100)]);
+    Encryption {
+        algorithm: encrypt::Algorithm::Aes128,
+        user: Some(encrypt::Secret::File(user)),
+        owner: None,
+        denied: Permissions::empty(),
+    }
+    .apply(&mut doc)
+    .unwrap();
+    let path = dir.join("locked.pdf");
+    doc.save(&path).unwrap();
+    let load = |passwords: &Passwords, listed: &[&str]| {
+        let listed = listed.iter().map(|p| p.to_string()).collect::<Vec<_>>();
+        passwords.load(&path, &listed, &Limits::NONE).map(|_| ())
+    };
+    // the listed passwords are tried in turn, then it stays locked (the prompt isn't allowed)
+    assert_eq!(load(&Passwords::default(), &["wrong", "right"]), Ok(()));
+    assert_eq!(
+        load(&Passwords::default(), &["wrong"]),
+        Err(MergeRunError::InputIsEncrypted(path.clone()))
+    );
+    // the variable named for the input (or its directory) is the only password tried
+    // SAFETY: no other test reads these variables
+    unsafe {
+        env::set_var("PDMERS_TEST_UNLOCK_RIGHT", "right");
+        env::set_var("PDMERS_TEST_UNLOCK_WRONG", "wrong");
+    }
+    let env = |var: &str| Passwords {
+        env: HashMap::from([(dir.clone(), var.into())]),
+        ..Default::default()
+    };
+    assert_eq!(load(&env("PDMERS_TEST_UNLOCK_RIGHT"), &["wrong"]), Ok(()));
+    assert_eq!(
+        load(&env("PDMERS_TEST_UNLOCK_WRONG"), &["right"]),
+        Err(MergeRunError::WrongInputPassword(path.clone()))
+    );
+    assert_eq!(
+        load(&env("PDMERS_TEST_UNLOCK_UNSET"), &["right"]),
+        Err(MergeRunError::PasswordEnvIsNotSet(
+            "PDMERS_TEST_UNLOCK_UNSET".into()
+        ))
+    );
+}
+
+#[test]
+fn encrypted_output_permissions() {
+    let dir = fixture_dir("encrypted-output");
+    let path = |name: &str| dir.join(name).to_string_lossy().into_owned();
+    let (a, b, out, user) = (
+        path("a.pdf"),
+        path("b.pdf"),
+        path("out.pdf"),
+        path("user.txt"),
+    );
+    document(&[(100, 100)]).save(&a).unwrap();
+    document(&[(200, 200)]).save(&b).unwrap();
+    fs::write(&user, "secret").unwrap();
+    let user = format!("file:{user}");
+    let args = [
+        "-i",
+        &a,
+        &b,
+        "-o",
+        &out,
+        "--encrypt",
+        "--user-password",
+        &user,
+    ];
+    merge(&[args.as_slice(), &["--deny", "print,copy"]].concat()).unwrap();
+
+    let bytes = fs::read(&out).unwrap();
+    let locked = Document::load_mem(&bytes).unwrap();
+    assert!(locked.is_encrypted() && locked.encryption_state.is_none());
+    let doc = Document::load_mem_with_password(&bytes, "secret").unwrap();
+    assert_eq!(doc.get_pages().len(), 2);
+    let permissions = doc.encryption_state.unwrap().permissions();
+    assert!(!permissions.intersects(Permissions::PRINTABLE | Permissions::COPYABLE));
+    assert!(permissions.contains(Permissions::MODIFIABLE | Permissions::FILLABLE));
+}
+
+#[test]
+fn limits_exceeded() {
+    let dir = fixture_dir("limits");
+    let path = |name: &str| dir.join(name).to_string_lossy().into_owned();
+    let (a, b, out) = (path("a.pdf"), path("b.pdf"), path("out.pdf"));
+    let mut doc = document(&[(100, 100), (100, 100), (100, 100)]);
+    let page_id = doc.page_iter().next().unwrap();
+    let content = doc.add_object(Stream::new(dictionary! {}, vec![b' '; 5000]));
+    let page = doc.get_dictionary_mut(page_id).unwrap();
+    page.set("Contents", content);
+    let nested = Object::Array(vec![Object::Array(vec![Object::Array(vec![])])]);
+    page.set("PieceInfo", nested);
+    doc.compress();
+    doc.save(&a).unwrap();
+    document(&[(100, 100)]).save(&b).unwrap();
+    [
+        ("--max-input-size", "100", Limit::InputSize(100)),
+        ("--max-nesting", "3", Limit::Nesting(3)),
+        ("--max-objects", "5", Limit::Objects(5)),
+        ("--max-pages", "2", Limit::Pages(2)),
+        ("--max-stream-bytes", "1000", Limit::StreamBytes(1000)),
+    ]
+    .into_iter()
+    .for_each(|(flag, value, limit)| {
+        let result = merge(&["-i", &a, &b, "-o", &out, flag, value]).map(|_| ());
+        assert_eq!(
+            result,
+            Err(MergeRunError::InputExceedsLimit(a.clone().into(), limit))
+        );
+    });
+    // within every limit
+    let limits = [
+        "--max-input-size=100000",
+        "--max-nesting=4",
+        "--max-objects=100",
+        "--max-pages=3",
+        "--max-stream-bytes=5000",
+    ];
+    assert!(merge(&[["-i", &a, &b, "-o", &out].as_slice(), &limits].concat()).is_ok());
+}
+
+#[cfg(unix)]
+#[test]
+fn root_escapes() {
+    use std::os::unix::fs::symlink;
+    let dir = fixture_dir("root");
+    let path = |name: &str| dir.join(name).to_string_lossy().into_owned();
+    fs::create_dir_all(dir.join("root/sub")).unwrap();
+    document(&[(100, 100)]).save(path("root/a.pdf")).unwrap();
+    document(&[(100, 100)]).save(path("root/b.pdf")).unwrap();
+    document(&[(100, 100)]).save(path("outside.pdf")).unwrap();
+    let (root, out) = (path("root"), path("root/out.pdf"));
+    let sandboxed = |inputs: &[&str]| {
+        let args = [
+            &["merge", "-i"],
+            inputs,
+            &["-o", &out, "-d", "*", "--root", &root],
+        ]
+        .concat();
+        Merge::try_from(MergeArgs::try_parse_from(args).unwrap()).unwrap()
+    };
+    let outside = |path: String| Err(MergeCheckError::PathIsOutsideRoot(path.into()));
+    // `..` references are allowed within the root, not out of it
+    let merge = sandboxed(&[&path("root/sub/../a.pdf"), &path("root/b.pdf")]);
+    assert_eq!(merge.check_item(), Ok(()));
+    assert!(merge.run_item().is_ok());
+    fs::remove_file(&out).unwrap();
+    let escape = path("root/../outside.pdf");
+    assert_eq!(
+        sandboxed(&[&path("root/a.pdf"), &escape]).check_item(),
+        outside(escape)
+    );
+    // neither are the symlinks leading out of it, listed or found within a directory
+    symlink(dir.join("outside.pdf"), dir.join("root/sub/link.pdf")).unwrap();
+    let link = path("root/sub/link.pdf");
+    assert_eq!(
+        sandboxed(&[&path("root/a.pdf"), &link]).check_item(),
+        outside(link)
+    );
+    let merge = sandboxed(&[&root]);
+    assert_eq!(merge.check_item(), Ok(()));
+    assert_eq!(
+        merge.run_item().map(|_| ()),
+        Err(MergeRunError::PathIsOutsideRoot(
+            dir.join("root/sub/link.pdf")
+        ))
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn symlink_loop() {
+    use std::os::unix::fs::symlink;
+    let dir = fixture_dir("symlink-loop");
+    let inputs = dir.join("inputs");
+    fs::create_dir_all(inputs.join("sub")).unwrap();
+    document(&[(100, 100)]).save(inputs.join("a.pdf")).unwrap();
+    document(&[(100, 100)])
+        .save(inputs.join("sub/b.pdf"))
+        .unwrap();
+    symlink(&inputs, inputs.join("sub/loop")).unwrap();
+    let (inputs, out) = (inputs.to_string_lossy(), dir.join("out.pdf"));
+    let args = ["-i", &inputs, "-o", &out.to_string_lossy(), "-d", "*"];
+    let success = merge(&[args.as_slice(), &["--follow-symlinks", "all"]].concat()).unwrap();
+    // each file is merged once, and the link back is reported
+    let mut files = success.files;
+    files.sort();
+    assert_eq!(
+        files,
+        [dir.join("inputs/a.pdf"), dir.join("inputs/sub/b.pdf")]
+    );
+    let skipped = &success.reports[0];
+    assert_eq!(skipped.title, "Skipped symlinks");
+    assert_eq!(skipped.items.len(), 1);
+    assert!(skipped.items[0].contains("directory already walked"));
+}
+
+#[test]
+fn rerun_leaves_out_previous_output() {
+    let dir = fixture_dir("rerun");
+    document(&[(100, 100)]).save(dir.join("a.pdf")).unwrap();
+    document(&[(100, 100)]).save(dir.join("b.pdf")).unwrap();
+    let input_dir = dir.to_string_lossy();
+    let run = |output: &str, flags: &[&str]| {
+        let output = dir.join(output).to_string_lossy().into_owned();
+        let args = ["-i", &input_dir, "-o", &output, "-d", "1"];
+        let mut files = merge(&[args.as_slice(), flags].concat()).unwrap().files;
+        files.sort();
+        files
+    };
+    let inputs = [dir.join("a.pdf"), dir.join("b.pdf")];
+    assert_eq!(run("merged.pdf", &[]), inputs);
+    // the marker is a private key, the producer isn't touched
+    let merged = Document::load(dir.join("merged.pdf")).unwrap();
+    let info = merged
+        .trailer
+        .get(b"Info")
+        .and_then(|info| merged.dereference(info))
+        .and_then(|(_, info)| info.as_dict())
+        .unwrap();
+    assert!(
+        info.get(b"PdmersGenerated")
+            .and_then(Object::as_bool)
+            .unwrap()
+    );
+    assert!(!info.has(b"Producer"));
+    // neither the output being overridden nor the one of an earlier run is merged
+    assert_eq!(run("merged.pdf", &["--override"]), inputs);
+    assert_eq!(run("merged-2.pdf", &[]), inputs);
+    // unless the generated files are kept
+    assert_eq!(
+        run("merged-3.pdf", &["--keep-generated"]),
+        [
+            &inputs[..],
+            &[dir.join("merged-2.pdf"), dir.join("merged.pdf")]
+        ]
+        .concat()
+    );
+}
+
+#[test]
+fn nup_cells() {
+    let dir = fixture_dir("nup");
+    let path = |name: &str| dir.join(name).to_string_lossy().into_owned();
+    let (a, b, out) = (path("a.pdf"), path("b.pdf"), path("out.pdf"));
+    document(&[(200, 300); 3]).save(&a).unwrap();
+    document(&[(200, 300); 2]).save(&b).unwrap();
+    let args = [
+        "-i",
+        &a,
+        &b,
+        "-o",
+        &out,
+        "--nup",
+        "2x2",
+        "--nup-paper",
+        "400x600pt",
+    ];
+    merge(&[args.as_slice(), &["--nup-gutter", "20"]].concat()).unwrap();
+
+    let doc = Document::load(&out).unwrap();
+    let sheets = doc.page_iter().collect::<Vec<_>>();
+    assert_eq!(sheets.len(), 2);
+    for sheet in &sheets {
+        assert_eq!(
+            page_box(&doc, *sheet, b"MediaBox").unwrap(),
+            [0.0, 0.0, 400.0, 600.0]
+        );
+    }
+    // 190x290 cells, the pages are scaled by 0.95 and centered within them
+    let cells = placements(&doc, sheets[0]);
+    let expected = [(0.0, 312.5), (210.0, 312.5), (0.0, 2.5), (210.0, 2.5)];
+    assert_eq!(cells.len(), 4);
+    for (cell, (x, y)) in cells.iter().zip(expected) {
+        assert!(approx(cell, &[0.95, 0.0, 0.0, 0.95, x, y]), "{cell:?}");
+    }
+    assert_eq!(placements(&doc, sheets[1]).len(), 1);
+}
+
+#[test]
+fn booklet_sides() {
+    let dir = fixture_dir("booklet");
+    let path = |name: &str| dir.join(name).to_string_lossy().into_owned();
+    let (a, out) = (path("a.pdf"), path("out.pdf"));
+    document(&[(190, 300); 6]).save(&a).unwrap();
+    let args = [
+        "-i",
+        &a,
+        "-o",
+        &out,
+        "--booklet",
+        "--booklet-paper",
+        "300x400pt",
+    ];
+    let flags = ["--booklet-gutter", "20", "--booklet-creep", "2"];
+    merge(&[args.as_slice(), &flags].concat()).unwrap();
+
+    // padded to 8 pages, two sheets printed on both sides
+    let doc = Document::load(&out).unwrap();
+    let sides = doc.page_iter().collect::<Vec<_>>();
+    let order = sides
+        .iter()
+        .map(|side| drawn(&doc, *side))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        order,
+        [vec!["P1"], vec!["P2"], vec!["P6", "P3"], vec!["P4", "P5"]]
+    );
+    // 190pt wide cells on both sides of the gutter, the inner sheet shifted by the creep
+    let xs = [vec![210.0], vec![0.0], vec![2.0, 208.0], vec![2.0, 208.0]];
+    for (side, xs) in sides.iter().zip(xs) {
+        assert_eq!(
+            page_box(&doc, *side, b"MediaBox").unwrap(),
+            [0.0, 0.0, 400.0, 300.0]
+        );
+        let cells = placements(&doc, *side);
+        assert_eq!(cells.len(), xs.len());
+        for (cell, x) in cells.iter().zip(xs) {
+            assert!(approx(cell, &[1.0, 0.0, 0.0, 1.0, x, 0.0]), "{cell:?}");
+        }
+    }
+    // the inner sheet can't be shifted past the fold (half the gutter)
+    let flags = [
+        "--booklet-gutter",
+        "20",
+        "--booklet-creep",
+        "11",
+        "--override",
+    ];
+    assert!(
+        merge(&[args.as_slice(), &flags].concat())
+            .is_err_and(|e| e == MergeRunError::BookletCreepTooLarge(11.0, 2))
+    );
+}
+
+#[test]
+fn tile_grid() {
+    let dir = fixture_dir("tile");
+    let path = |name: &str| dir.join(name).to_string_lossy().into_owned();
+    let (a, out) = (path("a.pdf"), path("out.pdf"));
+    document(&[(500, 700), (200, 300)]).save(&a).unwrap();
+    let args = ["-i", &a, "-o", &out, "--tile", "300x400pt"];
+    merge(
+        &[
+            args.as_slice(),
+            &["--tile-overlap", "10", "--tile-margin", "20"],
+        ]
+        .concat(),
+    )
+    .unwrap();
+
+    // 260x360 printable areas: the large page needs 2x2 portrait sheets, the small one fits
+    let doc = Document::load(&out).unwrap();
+    let tiles = doc.page_iter().collect::<Vec<_>>();
+    assert_eq!(tiles.len(), 5);
+    let offsets = [
+        (20.0, -320.0),
+        (-230.0, -320.0),
+        (20.0, 30.0),
+        (-230.0, 30.0),
+        (20.0, 80.0),
+    ];
+    for (tile, (x, y)) in tiles.iter().zip(offsets) {
+        assert_eq!(
+            page_box(&doc, *tile, b"MediaBox").unwrap(),
+            [0.0, 0.0, 300.0, 400.0]
+        );
+        let content = Content::decode(&doc.get_page_content(*tile).unwrap()).unwrap();
+        let shift = content
+            .operations
+            .iter()
+            .find(|op| op.operator == "cm")
+            .unwrap();
+        let shift = shift
+            .operands
+            .iter()
+            .map(|o| o.as_float().unwrap())
+            .collect::<Vec<_>>();
+        assert!(approx(&shift, &[1.0, 0.0, 0.0, 1.0, x, y]), "{shift:?}");
+    }
+}
+
+#[test]
+fn page_size_fit() {
+    let dir = fixture_dir("page-size");
+    let path = |name: &str| dir.join(name).to_string_lossy().into_owned();
+    let (a, out) = (path("a.pdf"), path("out.pdf"));
+    document(&[(200, 300), (600, 400), (300, 300)])
+        .save(&a)
+        .unwrap();
+    let success = merge(&["-i", &a, "-o", &out, "--page-size", "400x600pt"]).unwrap();
+
+    // each page takes the paper size in its own orientation, its content scaled and centered
+    let doc = Document::load(&out).unwrap();
+    let pages = doc.page_iter().collect::<Vec<_>>();
+    let boxes = [
+        [0.0, 0.0, 400.0, 600.0],
+        [0.0, 0.0, 600.0, 400.0],
+        [0.0, 0.0, 400.0, 600.0],
+    ];
+    for (page_id, media_box) in pages.iter().zip(boxes) {
+        assert_eq!(page_box(&doc, *page_id, b"MediaBox").unwrap(), media_box);
+    }
+    assert!(approx(
+        &placements(&doc, pages[0])[0],
+        &[2.0, 0.0, 0.0, 2.0, 0.0, 0.0]
+    ));
+    assert!(placements(&doc, pages[1]).is_empty());
+    let scale = 400.0 / 300.0;
+    assert!(approx(
+        &placements(&doc, pages[2])[0],
+        &[scale, 0.0, 0.0, scale, 0.0, 100.0]
+    ));
+    let resized = success
+        .reports
+        .iter()
+        .find(|r| r.title.starts_with("Resized"))
+        .unwrap();
+    assert_eq!(resized.items.len(), 2);
+}
+
+#[test]
+fn orient_portrait() {
+    let dir = fixture_dir("orient");
+    let path = |name: &str| dir.join(name).to_string_lossy().into_owned();
+    let (a, out) = (path("a.pdf"), path("out.pdf"));
+    document(&[(300, 200), (200, 300), (200, 200)])
+        .save(&a)
+        .unwrap();
+    merge(&["-i", &a, "-o", &out, "--orient", "portrait"]).unwrap();
+
+    // only the landscape page is turned, its boxes are kept
+    let doc = Document::load(&out).unwrap();
+    let pages = doc.page_iter().collect::<Vec<_>>();
+    let rotate = |page_id| {
+        let page = doc.get_dictionary(page_id).unwrap();
+        page.get(b"Rotate").and_then(Object::as_i64).unwrap_or(0)
+    };
+    assert_eq!(
+        pages.iter().map(|id| rotate(*id)).collect::<Vec<_>>(),
+        [90, 0, 0]
+    );
+    assert_eq!(
+        page_box(&doc, pages[0], b"MediaBox").unwrap(),
+        [0.0, 0.0, 300.0, 200.0]
+    );
+}
+
+#[test]
+fn orient_turns_back() {
+    let doc = Document::new();
+    // (size, rotation) -> rotation once portrait
+    [
+        ((300, 200), 0, 90),
+        ((300, 200), 180, 90),
+        ((200, 300), 90, 0),
+        ((200, 300), 270, 0),
+        ((200, 300), -90, 0),
+    ]
+    .into_iter()
+    .for_each(|((width, height), rotate, expected)| {
+        let mut page = dictionary! {
+            "Type" => "Page",
+            "MediaBox" => vec![0.into(), 0.into(), width.into(), height.into()],
+            "Rotate" => rotate,
+        };
+        Orientation::Portrait.apply(&doc, &mut page);
+        assert_eq!(
+            page.get(b"Rotate").unwrap().as_i64().unwrap(),
+            expected,
+            "{rotate}"
+        );
+    });
+}
+
+#[test]
+fn crop_and_margin_boxes() {
+    let dir = fixture_dir("crop");
+    let path = |name: &str| dir.join(name).to_string_lossy().into_owned();
+    let (a, out) = (path("a.pdf"), path("out.pdf"));
+    document(&[(200, 300), (200, 300)]).save(&a).unwrap();
+    let args = ["-i", &a, "-o", &out, "--override", "--crop", "10,20,30,40"];
+    merge(&args).unwrap();
+
+    // top, right, bottom, left margins cut from the page
+    let doc = Document::load(&out).unwrap();
+    for page_id in doc.page_iter() {
+        for key in [b"CropBox".as_slice(), b"TrimBox"] {
+            assert_eq!(
+                page_box(&doc, page_id, key).unwrap(),
+                [40.0, 30.0, 180.0, 290.0]
+            );
+        }
+    }
+    // the cropped page grows by the binding margin, on the left of the odd pages
+    merge(&[args.as_slice(), &["--add-margin", "15"]].concat()).unwrap();
+    let doc = Document::load(&out).unwrap();
+    let pages = doc.page_iter().collect::<Vec<_>>();
+    for (page_id, x) in pages.iter().zip([-25.0, -40.0]) {
+        assert_eq!(
+            page_box(&doc, *page_id, b"MediaBox").unwrap(),
+            [0.0, 0.0, 155.0, 260.0]
+        );
+        assert_eq!(page_box(&doc, *page_id, b"CropBox"), None);
+        let shift = &placements(&doc, *page_id)[0];
+        assert!(approx(shift, &[1.0, 0.0, 0.0, 1.0, x, -30.0]), "{shift:?}");
+    }
+}
+
+#[test]
+fn crop_reports_pages_too_small() {
+    let mut doc = document(&[(200, 300), (50, 300)]);
+    let origins = [1, 2].map(|page| PageOrigin {
+        file: "a.pdf".into(),
+        page,
+    });
+    let report = Crop::Margins([10.0, 30.0, 10.0, 30.0])
+        .apply(&mut doc, &origins)
+        .unwrap();
+    assert_eq!(report.items.len(), 1);
+    assert!(report.items[0].contains("(page 2)"));
+    let pages = doc.page_iter().collect::<Vec<_>>();
+    assert_eq!(
+        page_box(&doc, pages[0], b"CropBox").unwrap(),
+        [30.0, 10.0, 170.0, 290.0]
+    );
+    assert_eq!(page_box(&doc, pages[1], b"CropBox"), None);
+}
+
+#[test]
+fn print_marks_boxes() {
+    let dir = fixture_dir("print-marks");
+    let path = |name: &str| dir.join(name).to_string_lossy().into_owned();
+    let (a, out) = (path("a.pdf"), path("out.pdf"));
+    document(&[(200, 300)]).save(&a).unwrap();
+    merge(&["-i", &a, "-o", &out, "--print-marks", "--bleed", "5"]).unwrap();
+
+    // the page grows by the bleed and the 36pt slug on every side
+    let doc = Document::load(&out).unwrap();
+    let page_id = doc.page_iter().next().unwrap();
+    let boxes = [
+        (b"MediaBox".as_slice(), [0.0, 0.0, 282.0, 382.0]),
+        (b"BleedBox", [36.0, 36.0, 246.0, 346.0]),
+        (b"TrimBox", [41.0, 41.0, 241.0, 341.0]),
+    ];
+    for (key, expected) in boxes {
+        assert_eq!(page_box(&doc, page_id, key).unwrap(), expected);
+    }
+    let shift = &placements(&doc, page_id)[0];
+    assert!(
+        approx(shift, &[1.0, 0.0, 0.0, 1.0, 41.0, 41.0]),
+        "{shift:?}"
+    );
+}
+
+#[test]
+fn drop_blank_pages() {
+    let dir = fixture_dir("drop-blank");
+    let path = |name: &str| dir.join(name).to_string_lossy().into_owned();
+    let (a, out) = (path("a.pdf"), path("out.pdf"));
+    let mut doc = document(&[(100, 100); 4]);
+    // black, none, white and light gray squares
+    let drawings = ["0 g", "", "1 g", "0.95 g"];
+    for (page_id, drawing) in doc
+        .page_iter()
+        .collect::<Vec<_>>()
+        .into_iter()
+        .zip(drawings)
+    {
+        let content = doc
+            .get_dictionary(page_id)
+            .unwrap()
+            .get(b"Contents")
+            .unwrap();
+        let content = doc.get_object_mut(content.as_reference().unwrap()).unwrap();
+        let stream = content.as_stream_mut().unwrap();
+        let square = match drawing {
+            "" => Vec::new(),
+            _ => format!(" {drawing} 10 10 80 80 re f").into_bytes(),
+        };
+        stream.set_content([stream.content.clone(), square].concat());
+    }
+    doc.save(&a).unwrap();
+    let success = merge(&["-i", &a, "-o", &out, "--drop-blank"]).unwrap();
+
+    assert_eq!(labels(&Document::load(&out).unwrap()), ["P1", "P4"]);
+    let removed = success
+        .reports
+        .iter()
+        .find(|r| r.title.contains("blank"))
+        .unwrap();
+    assert_eq!(removed.items.len(), 2);
+    assert!(removed.items[0].contains("(page 2)") && removed.items[1].contains("(page 3)"));
+    // the light gray square is white enough with a higher sensitivity
+    let args = [
+        "-i",
+        &a,
+        "-o",
+        &out,
+        "--drop-blank",
+        "--blank-sensitivity",
+        "0.9",
+    ];
+    merge(&args).unwrap();
+    assert_eq!(labels(&Document::load(&out).unwrap()), ["P1"]);
+}
+
+#[test]
+fn order_and_reverse() {
+    let dir = fixture_dir("order");
+    let path = |name: &str| dir.join(name).to_string_lossy().into_owned();
+    let (a, b, order, out) = (
+        path("a.pdf"),
+        path("b.pdf"),
+        path("order.txt"),
+        path("out.pdf"),
+    );
+    document(&[(100, 100); 3]).save(&a).unwrap();
+    document(&[(200, 200)]).save(&b).unwrap();
+    fs::write(
+        &order,
+        "# cover first\nb.pdf:1\na.pdf:3\n\na.pdf:1\na.pdf:1\n",
+    )
+    .unwrap();
+    merge(&["-i", &a, &b, "-o", &out, "--order", &order]).unwrap();
+
+    // pages can be left out (`a.pdf:2`) or repeated
+    let doc = Document::load(&out).unwrap();
+    assert_eq!(labels(&doc), ["P1", "P3", "P1", "P1"]);
+    let sizes = doc
+        .page_iter()
+        .map(|id| page_box(&doc, id, b"MediaBox").unwrap()[2]);
+    assert_eq!(sizes.collect::<Vec<_>>(), [200.0, 100.0, 100.0, 100.0]);
+    merge(&["-i", &a, &b, "-o", &out, "--order", &order, "--reverse"]).unwrap();
+    let doc = Document::load(&out).unwrap();
+    let sizes = doc
+        .page_iter()
+        .map(|id| page_box(&doc, id, b"MediaBox").unwrap()[2]);
+    assert_eq!(sizes.collect::<Vec<_>>(), [100.0, 100.0, 100.0, 200.0]);
+    assert_eq!(labels(&doc), ["P1", "P1", "P3", "P1"]);
+}
+
+#[test]
+fn order_copies_repeated_annotations() {
+    let dir = fixture_dir("order-annots");
+    let path = |name: &str| dir.join(name).to_string_lossy().into_owned();
+    let (a, b, order, out) = (
+        path("a.pdf"),
+        path("b.pdf"),
+        path("order.txt"),
+        path("out.pdf"),
+    );
+    let mut doc = document(&[(100, 100)]);
+    let page_id = doc.page_iter().next().unwrap();
+    let rect = || vec![0.into(), 0.into(), 10.into(), 10.into()];
+    let note = doc.add_object(dictionary! {
+        "Type" => "Annot",
+        "Subtype" => "Text",
+        "Rect" => rect(),
+        "P" => page_id,
+    });
+    let popup = doc.add_object(dictionary! {
+        "Type" => "Annot",
+        "Subtype" => "Popup",
+        "Rect" => rect(),
+        "P" => page_id,
+        "Parent" => note,
+    });
+    doc.get_dictionary_mut(note).unwrap().set("Popup", popup);
+    doc.get_dictionary_mut(page_id)
+        .unwrap()
+        .set("Annots", vec![note.into(), popup.into()]);
+    doc.save(&a).unwrap();
+    document(&[(200, 200)]).save(&b).unwrap();
+    fs::write(&order, "a.pdf:1\nb.pdf:1\na.pdf:1\n").unwrap();
+    merge(&["-i", &a, &b, "-o", &out, "--order", &order]).unwrap();
+
+    // each copy of the page has its own annotations, pointing to it and linked to each other
+    let doc = Document::load(&out).unwrap();
+    let pages = doc.page_iter().collect::<Vec<_>>();
+    let annots = |page_id: ObjectId| {
+        doc.get_dictionary(page_id)
+            .unwrap()
+            .get(b"Annots")
+            .unwrap()
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|a| a.as_reference().unwrap())
+            .collect::<Vec<_>>()
+    };
+    let (first, copy) = (annots(pages[0]), annots(pages[2]));
+    assert!(first.iter().all(|id| !copy.contains(id)));
+    for (page_id, annots) in [(pages[0], first), (pages[2], copy)] {
+        let [note, popup] = annots[..] else {
+            panic!("the page should have a note and its popup")
+        };
+        let (note_dict, popup_dict) = (
+            doc.get_dictionary(note).unwrap(),
+            doc.get_dictionary(popup).unwrap(),
+        );
+        for annot in [note_dict, popup_dict] {
+            assert_eq!(annot.get(b"P").unwrap().as_reference().unwrap(), page_id);
+        }
+        assert_eq!(
+            note_dict.get(b"Popup").unwrap().as_reference().unwrap(),
+            popup
+        );
+        assert_eq!(
+            popup_dict.get(b"Parent").unwrap().as_reference().unwrap(),
+            note
+        );
+    }
+}
+
+#[test]
+fn flatten_places_appearances() {
+    let mut doc = document(&[(200, 200)]);
+    let page_id = doc.page_iter().next().unwrap();
+    let form = doc.add_object(Stream::new(
+        dictionary! { "BBox" => vec![0.into(), 0.into(), 50.into(), 20.into()] },
+        b"0 g 0 0 50 20 re f".to_vec(),
+    ));
+    let rect = |r: [i64; 4]| r.map(Object::from).to_vec();
+    let annots = [
+        dictionary! {
+            "Type" => "Annot",
+            "Subtype" => "Widget",
+            "Rect" => rect([10, 20, 110, 60]),
+            "AP" => dictionary! { "N" => form },
+        },
+        dictionary! { "Type" => "Annot", "Subtype" => "Link", "Rect" => rect([0, 0, 10, 10]) },
+        dictionary! { "Type" => "Annot", "Subtype" => "Text", "Rect" => rect([0, 0, 10, 10]) },
+    ]
+    .map(|annot| Object::from(doc.add_object(annot)));
+    let link = annots[1].clone();
+    doc.get_dictionary_mut(page_id)
+        .unwrap()
+        .set("Annots", annots.to_vec());
+    let origins = [PageOrigin {
+        file: "a.pdf".into(),
+        page: 1,
+    }];
+    let report = flatten::apply(&mut doc, &origins).unwrap();
+
+    // the 50x20 appearance is stretched over the 100x40 rect, only the link is left
+    let placed = placements(&doc, page_id);
+    assert_eq!(placed.len(), 1);
+    assert!(
+        approx(&placed[0], &[2.0, 0.0, 0.0, 2.0, 10.0, 20.0]),
+        "{placed:?}"
+    );
+    let page = doc.get_dictionary(page_id).unwrap();
+    assert_eq!(page.get(b"Annots").unwrap(), &Object::Array(vec![link]));
+    assert_eq!(report.items.len(), 1);
+    assert!(report.items[0].contains("Text annotation"));
+}
@@ -0,0 +1,186 @@
+use super::{
+    MergeRunError,
+    page::{self, Geometry, PageOrigin},
+    paper::PaperSize,
+};
+use lopdf::{
+    Document, Object, Stream,
+    content::{Content, Operation},
+    dictionary,
+};
+use std::collections::BTreeMap;
+
+/// Font resource name used by the tile labels.
+const FONT_NAME: &[u8] = b"PdmersTileFont";
+/// Form XObject resource name of the tiled page.
+const PAGE_NAME: &[u8] = b"PdmersTiledPage";
+/// Label font size.
+const LABEL_SIZE: f32 = 8.0;
+/// Space between the printable area corners and the crop marks.
+const MARK_OFFSET: f32 = 3.0;
+
+/// Poster tiling options (each page is split into several sheets, at its real size).
+#[derive(Debug, PartialEq)]
+pub struct Tile {
+    /// Sheet size (the orientation that needs fewer sheets is used).
+    pub paper: PaperSize,
+    /// How much adjacent tiles overlap.
+    pub overlap: f32,
+    /// Sheet margin (where the crop marks and the label are drawn).
+    pub margin: f32,
+}
+
+impl Tile {
+    /// Grid (`rows`, `columns`) needed to tile a page of the given size on a sheet.
+    fn grid(&self, (width, height): (f32, f32), sheet: (f32, f32)) -> (u32, u32) {
+        let count = |page: f32, side: f32| {
+            let area = side - 2.0 * self.margin;
+            match page <= area {
+                true => 1,
+                false => ((page - self.overlap) / (area - self.overlap)).ceil() as u32,
+            }
+        };
+        (count(height, sheet.1), count(width, sheet.0))
+    }
+
+    /// Sheet size and grid for a page of the given size (the orientation needing fewer sheets).
+    fn layout(&self, page: (f32, f32)) -> ((f32, f32), (u32, u32)) {
+        let portrait = self.paper.oriented(false);
+        let landscape = self.paper.oriented(true);
+        let (p, l) = (self.grid(page, portrait), self.grid(page, landscape));
+        match l.0 * l.1 < p.0 * p.1 {
+            true => (landscape, l),
+            false => (portrait, p),
+        }
+    }
+
+    /// Content of the tile at (`row`, `col`) (from the top-left corner of the page).
+    fn content(
+        &self,
+        geometry: &Geometry,
+        (width, height): (f32, f32),
+        (row, col): (u32, u32),
+        label: &str,
+    ) -> lopdf::Result<Vec<u8>> {
+        let (area_w, area_h) = (width - 2.0 * self.margin, height - 2.0 * self.margin);
+        let page_h = geometry.visual_size().1;
+        let x = col as f32 * (area_w - self.overlap);
+        let y = page_h - area_h - row as f32 * (area_h - self.overlap);
+        let mut operations = vec![
+            Operation::new("q", vec![]),
+            Operation::new(
+                "re",
+                vec![
+                    self.margin.into(),
+                    self.margin.into(),
+                    area_w.into(),
+                    area_h.into(),
+                ],
+            ),
+            Operation::new("W", vec![]),
+            Operation::new("n", vec![]),
+            Operation::new(
+                "cm",
+                vec![
+                    1.into(),
+                    0.into(),
+                    0.into(),
+                    1.into(),
+                    (self.margin - x).into(),
+                    (self.margin - y).into(),
+                ],
+            ),
+            Operation::new(
+                "cm",
+                geometry
+                    .user_matrix()
+                    .into_iter()
+                    .map(Object::Real)
+                    .collect(),
+            ),
+            Operation::new("Do", vec![Object::Name(PAGE_NAME.into())]),
+            Operation::new("Q", vec![]),
+        ];
+        let area = [
+            self.margin,
+            self.margin,
+            width - self.margin,
+            height - self.margin,
+        ];
+        operations.extend(page::crop_marks(
+            area,
+            MARK_OFFSET,
+            (self.margin - MARK_OFFSET) / 2.0,
+        ));
+        operations.extend([
+            Operation::new("BT", vec![]),
+            Operation::new(
+                "Tf",
+                vec![Object::Name(FONT_NAME.into()), LABEL_SIZE.into()],
+            ),
+            Operation::new(
+                "Td",
+                vec![
+                    self.margin.into(),
+                    ((self.margin - LABEL_SIZE) / 2.0).into(),
+                ],
+            ),
+            Operation::new("Tj", vec![Object::string_literal(label)]),
+            Operation::new("ET", vec![]),
+        ]);
+        Content { operations }.encode()
+    }
+
+    /// Splits each page into a grid of overlapping tiles (one per sheet) drawing the page at its
+    /// real size. The page becomes a form XObject clipped to each tile printable area.
+    ///
+    /// Every tile origin is the origin of its page.
+    pub fn apply(
+        &self,
+        doc: &mut Document,
+        origins: &mut Vec<PageOrigin>,
+    ) -> Result<(), MergeRunError> {
+        let err = |_| MergeRunError::CouldNotEditMergedPages("tile".into());
+        let font = doc.add_object(dictionary! {
+            "Type" => "Font",
+            "Subtype" => "Type1",
+            "BaseFont" => "Helvetica",
+        });
+        let mut tiles = Vec::new();
+        let mut tiles_origins = Vec::new();
+        let mut replaced = BTreeMap::new();
+        for ((number, page_id), origin) in doc.get_pages().into_iter().zip(origins.iter()) {
+            let geometry = Geometry::of(doc, page_id);
+            let (sheet, (rows, cols)) = self.layout(geometry.visual_size());
+            let form = doc.add_object(page::form_xobject(doc, page_id));
+            for row in 0..rows {
+                for col in 0..cols {
+                    let label = format!(
+                        "page {number} - row {} of {rows}, column {} of {cols}",
+                        row + 1,
+                        col + 1
+                    );
+                    let content = self
+                        .content(&geometry, sheet, (row, col), &label)
+                        .map_err(err)?;
+                    let contents = doc.add_object(Stream::new(lopdf::Dictionary::new(), content));
+                    let tile = doc.add_object(dictionary! {
+                        "Type" => "Page",
+                        "MediaBox" => vec![0.into(), 0.into(), sheet.0.into(), sheet.1.into()],
+                        "Resources" => dictionary! {
+                            "XObject" => dictionary! { PAGE_NAME => form },
+                            "Font" => dictionary! { FONT_NAME => font },
+                        },
+                        "Contents" => contents,
+                    });
+                    replaced.entry(page_id).or_insert(tile);
+                    tiles_origins.push(origin.clone());
+                    tiles.push(tile);
+                }
+            }
+        }
+        page::set_pages(doc, tiles, &replaced).map_err(err)?;
+        *origins = tiles_origins;
+        Ok(())
+    }
+}
@@ -0,0 +1,207 @@
+use super::{
+    MergeRunError,
+    image::{self, Image},
+    page::{self, Geometry, PageSelection},
+};
+use lopdf::{
+    Document, Object,
+    content::{Content, Operation},
+    dictionary,
+};
+use std::{path::PathBuf, str::FromStr};
+
+/// Font resource name used by the text watermark.
+const FONT_NAME: &[u8] = b"PdmersWatermarkFont";
+/// Graphics state resource name (opacity).
+const GS_NAME: &[u8] = b"PdmersWatermarkGs";
+/// Image resource name used by the image watermark.
+const IMAGE_NAME: &[u8] = b"PdmersWatermarkImage";
+/// Fraction of the page (smaller side) used by the image watermark.
+const IMAGE_FRACTION: f32 = 0.5;
+
+/// Helvetica glyph widths (chars from ` ` to `~`, 1/1000 text space units).
+const HELVETICA_WIDTHS: [u16; 95] = [
+    278, 278, 355, 556, 556, 889, 667, 222, 333, 333, 389, 584, 278, 333, 278, 278, 556, 556, 556,
+    556, 556, 556, 556, 556, 556, 556, 278, 278, 584, 584, 584, 556, 1015, 667, 667, 722, 722, 667,
+    611, 778, 722, 278, 500, 667, 556, 833, 722, 778, 667, 778, 722, 667, 611, 722, 667, 944, 667,
+    667, 611, 278, 278, 278, 469, 556, 222, 556, 556, 500, 556, 556, 278, 556, 556, 222, 222, 500,
+    222, 833, 556, 556, 556, 556, 333, 500, 278, 556, 500, 722, 500, 500, 500, 334, 260, 334, 584,
+];
+
+/// Text width when using the Helvetica font.
+fn text_width(text: &str, size: f32) -> f32 {
+    text.chars()
+        .map(|c| {
+            (c as usize)
+                .checked_sub(32)
+                .and_then(|i| HELVETICA_WIDTHS.get(i))
+                .copied()
+                .unwrap_or(556) as f32
+        })
+        .sum::<f32>()
+        * size
+        / 1000.0
+}
+
+/// A RGB color (parsed from a `RRGGBB` hex string).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Color(pub [f32; 3]);
+
+impl Default for Color {
+    fn default() -> Self {
+        Self([0.5, 0.5, 0.5])
+    }
+}
+
+impl FromStr for Color {
+    type Err = ();
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let hex = s.strip_prefix('#').unwrap_or(s);
+        if hex.len() != 6 || !hex.is_ascii() {
+            return Err(());
+        }
+        let mut rgb = [0.0; 3];
+        for (i, c) in rgb.iter_mut().enumerate() {
+            *c = u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16).map_err(|_| ())? as f32 / 255.0;
+        }
+        Ok(Self(rgb))
+    }
+}
+
+/// Text and/or image watermark options.
+#[derive(Debug, PartialEq)]
+pub struct Watermark {
+    /// Text to be drawn at the page center.
+    pub text: Option<String>,
+    /// Image (PNG/JPEG) to be drawn at the page center.
+    pub image: Option<PathBuf>,
+    /// Text font size.
+    pub size: f32,
+    /// Counterclockwise rotation (degrees).
+    pub rotation: f32,
+    /// Text color.
+    pub color: Color,
+    /// Opacity (from `0` to `1`).
+    pub opacity: f32,
+    /// Pages to be watermarked (all of them when empty).
+    pub pages: PageSelection,
+    /// Draw the watermark under the page content instead of over it.
+    pub under: bool,
+}
+
+impl Watermark {
+    /// Content that draws the watermark for a page with the given geometry.
+    fn content(&self, geometry: &Geometry, image: Option<&Image>) -> lopdf::Result<Vec<u8>> {
+        let (width, height) = geometry.visual_size();
+        let (sin, cos) = self.rotation.to_radians().sin_cos();
+        let mut operations = vec![
+            Operation::new("q", vec![]),
+            Operation::new("gs", vec![Object::Name(GS_NAME.into())]),
+            Operation::new(
+                "cm",
+                geometry
+                    .visual_matrix()
+                    .into_iter()
+                    .map(Object::Real)
+                    .collect(),
+            ),
+            Operation::new(
+                "cm",
+                vec![
+                    cos.into(),
+                    sin.into(),
+                    (-sin).into(),
+                    cos.into(),
+                    (width / 2.0).into(),
+                    (height / 2.0).into(),
+                ],
+            ),
+        ];
+        if let Some(image) = image {
+            let side = width.min(height) * IMAGE_FRACTION;
+            let scale = side / image.width.max(image.height) as f32;
+            let (w, h) = (image.width as f32 * scale, image.height as f32 * scale);
+            operations.extend([
+                Operation::new("q", vec![]),
+                Operation::new(
+                    "cm",
+                    vec![
+                        w.into(),
+                        0.into(),
+                        0.into(),
+                        h.into(),
+                        (-w / 2.0).into(),
+                        (-h / 2.0).into(),
+                    ],
+                ),
+                Operation::new("Do", vec![Object::Name(IMAGE_NAME.into())]),
+                Operation::new("Q", vec![]),
+            ]);
+        }
+        if let Some(text) = &self.text {
+            let [r, g, b] = self.color.0;
+            operations.extend([
+                Operation::new("rg", vec![r.into(), g.into(), b.into()]),
+                Operation::new("BT", vec![]),
+                Operation::new("Tf", vec![Object::Name(FONT_NAME.into()), self.size.into()]),
+                Operation::new(
+                    "Td",
+                    vec![
+                        (-text_width(text, self.size) / 2.0).into(),
+                        (-self.size * 0.35).into(),
+                    ],
+                ),
+                Operation::new("Tj", vec![Object::string_literal(text.as_str())]),
+                Operation::new("ET", vec![]),
+            ]);
+        }
+        operations.push(Operation::new("Q", vec![]));
+        Content { operations }.encode()
+    }
+
+    /// Draws the watermark on the selected pages.
+    pub fn apply(&self, doc: &mut Document) -> Result<(), MergeRunError> {
+        let err = |_| MergeRunError::CouldNotEditMergedPages("watermark".into());
+        let image = match &self.image {
+            Some(path) => Some(
+                image::add_image(doc, path)
+                    .ok_or_else(|| MergeRunError::CouldNotLoadWatermarkImage(path.clone()))?,
+            ),
+            None => None,
+        };
+        let gs = doc.add_object(dictionary! {
+            "Type" => "ExtGState",
+            "ca" => self.opacity,
+            "CA" => self.opacity,
+        });
+        let font = self.text.as_ref().map(|_| {
+            doc.add_object(dictionary! {
+                "Type" => "Font",
+                "Subtype" => "Type1",
+                "BaseFont" => "Helvetica",
+            })
+        });
+        for (number, page_id) in doc.get_pages() {
+            if !self.pages.contains(number) {
+                continue;
+            }
+            let content = self
+                .content(&Geometry::of(doc, page_id), image.as_ref())
+                .map_err(err)?;
+            page::add_resource(doc, page_id, b"ExtGState", GS_NAME, gs.into()).map_err(err)?;
+            if let Some(font) = font {
+                page::add_resource(doc, page_id, b"Font", FONT_NAME, font.into()).map_err(err)?;
+            }
+            if let Some(image) = &image {
+                page::add_resource(doc, page_id, b"XObject", IMAGE_NAME, image.id.into())
+                    .map_err(err)?;
+            }
+            if self.under {
+                page::stamp_under(doc, page_id, content).map_err(err)?;
+            } else {
+                page::stamp_over(doc, page_id, content).map_err(err)?;
+            }
+        }
+        Ok(())
+    }
+}
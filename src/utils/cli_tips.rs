@@ -22,6 +22,58 @@ pub fn merge_depth() {
     Printer::echoln(format!("or the infinity repr (`{}`).", "*".green()));
 }
 
+pub fn merge_orient() {
+    Printer::echoln(format!(
+        "The `{}` flag expects `{}` or `{}`.",
+        "--orient".green(),
+        "portrait".cyan(),
+        "landscape".cyan()
+    ));
+}
+
+pub fn merge_bleed() {
+    Printer::echoln(format!(
+        "The `{}` flag expects a non negative number (in points).",
+        "--bleed".green()
+    ));
+}
+
+pub fn merge_blank_sensitivity() {
+    Printer::echoln(format!(
+        "The `{}` flag expects a number from {} (only pure white",
+        "--blank-sensitivity".green(),
+        "0".cyan()
+    ));
+    Printer::echoln(format!("is blank) to {} (any color is blank).", "1".cyan()));
+}
+
+pub fn merge_dedupe_by() {
+    Printer::echoln(format!(
+        "The `{}` flag expects `{}` (same path), `{}` (same",
+        "--dedupe-by".green(),
+        "path".cyan(),
+        "inode".cyan()
+    ));
+    Printer::echoln(format!(
+        "file on disk) or `{}` (same file content).",
+        "hash".cyan()
+    ));
+}
+
+pub fn merge_follow_symlinks() {
+    Printer::echoln(format!(
+        "The `{}` flag expects `{}`, `{}` (links to",
+        "--follow-symlinks".green(),
+        "never".cyan(),
+        "files".cyan()
+    ));
+    Printer::echoln(format!(
+        "files only) or `{}` (links to files and directories).",
+        "all".cyan()
+    ));
+    Printer::echoln("The skipped links are listed after merging.");
+}
+
 /// Tips when no args provided.
 pub fn help_tip() {
     Printer::echoln(format!(
@@ -36,7 +88,11 @@ pub fn directory_references() {
         "By default, directory references {} (this avoid path",
         "aren't allowed".red()
     ));
-    Printer::echoln("exploit and infinity directory traversal).");
+    Printer::echoln("exploit and infinity directory traversal). They're allowed");
+    Printer::echoln(format!(
+        "within a sandbox given by `{}` instead.",
+        "--root".green()
+    ));
     Printer::blankln(1);
     Printer::echoln(format!(
         "You should avoid '{}' for current path. Just use the dir/file",
@@ -146,3 +202,394 @@ pub fn could_not_save_pdf() {
     Printer::blankln(1);
     Printer::echoln(format!("Can also be mem. issue {}", "._.".cyan()));
 }
+
+/// Tips for `--bates` options usage.
+pub fn bates_usage() {
+    Printer::echoln(format!(
+        "The `{}` flag stamps a numbered label on every merged page.",
+        "--bates".green()
+    ));
+    Printer::echoln(format!(
+        "`{}` and `{}` must be printable ASCII text,",
+        "--bates".green(),
+        "--bates-suffix".green()
+    ));
+    Printer::echoln(format!(
+        "`{}` and `{}` must be positive numbers, and",
+        "--bates-start".green(),
+        "--bates-digits".green()
+    ));
+    Printer::echoln(format!(
+        "`{}` must be one of `{}`, `{}` or `{}`",
+        "--bates-position".green(),
+        "top-left".cyan(),
+        "top-center".cyan(),
+        "top-right".cyan()
+    ));
+    Printer::echoln(format!("(or the `{}` ones).", "bottom-*".cyan()));
+    Printer::blankln(1);
+    Printer::echoln(format!(
+        "The `{}` must be a csv file path.",
+        "--bates-log".green()
+    ));
+    Printer::blankln(1);
+    Printer::echoln(format!(
+        "{}: `{}`",
+        "ie".green(),
+        "pdmers -i docs -o prod.pdf -d 1 --bates ACME --bates-log prod.csv".cyan()
+    ))
+}
+
+/// Tips for `--watermark-*` options usage.
+pub fn watermark_usage() {
+    Printer::echoln(format!(
+        "Use `{}` and/or `{}` (png/jpeg file) to",
+        "--watermark-text".green(),
+        "--watermark-image".green()
+    ));
+    Printer::echoln("draw a watermark at the center of the pages.");
+    Printer::blankln(1);
+    Printer::echoln(format!(
+        "`{}` must be a positive number, `{}` a number",
+        "--watermark-size".green(),
+        "--watermark-rotation".green()
+    ));
+    Printer::echoln(format!(
+        "of degrees, `{}` a hex color ({}) and",
+        "--watermark-color".green(),
+        "RRGGBB".cyan()
+    ));
+    Printer::echoln(format!(
+        "`{}` a number from {} to {}.",
+        "--watermark-opacity".green(),
+        "0".cyan(),
+        "1".cyan()
+    ));
+    Printer::echoln(format!(
+        "`{}` expects page ranges (`{}`).",
+        "--watermark-pages".green(),
+        "1-3,5,8-".cyan()
+    ));
+    Printer::blankln(1);
+    Printer::echoln(format!(
+        "{}: `{}`",
+        "ie".green(),
+        "pdmers -i a.pdf b.pdf -o out.pdf --watermark-text DRAFT".cyan()
+    ))
+}
+
+/// Tips for encrypted inputs.
+pub fn password_usage() {
+    Printer::echoln("Encrypted inputs need a password. It can be read from an");
+    Printer::echoln(format!(
+        "environment variable (`{}`), tried",
+        "secret.pdf#password-env=VAR".cyan()
+    ));
+    Printer::echoln(format!(
+        "from a `{}` (one per line) or asked with",
+        "--password-file".green()
+    ));
+    Printer::echoln(format!("`{}`.", "--password-prompt".green()));
+    Printer::blankln(1);
+    Printer::echoln(format!(
+        "{}: `{}`",
+        "ie".green(),
+        "pdmers -i a.pdf secret.pdf#password-env=PASS -o out.pdf".cyan()
+    ))
+}
+
+/// Tips for `--order` flag usage.
+pub fn order_usage() {
+    Printer::echoln(format!(
+        "The `{}` flag expects an existing text file listing one",
+        "--order".green()
+    ));
+    Printer::echoln(format!(
+        "page reference per line (`{}`, in the wanted order).",
+        "file.pdf:N".cyan()
+    ));
+    Printer::echoln("The file can be the input path or its file name (when it");
+    Printer::echoln("isn't shared by other inputs).");
+    Printer::blankln(1);
+    Printer::echoln(format!(
+        "{}: `{}`",
+        "ie".green(),
+        "pdmers -i a.pdf b.pdf -o out.pdf --order pages.txt".cyan()
+    ))
+}
+
+/// Tips for `--underlay` and `--overlay` flags usage.
+pub fn stamp_usage() {
+    Printer::echoln(format!(
+        "The `{}` and `{}` flags expect an existing pdf file.",
+        "--underlay".green(),
+        "--overlay".green()
+    ));
+    Printer::echoln("The page N of this file is drawn on the page N of the");
+    Printer::echoln("merged file (the last page is repeated when needed).");
+    Printer::blankln(1);
+    Printer::echoln(format!(
+        "{}: `{}`",
+        "ie".green(),
+        "pdmers -i a.pdf b.pdf -o out.pdf --underlay letterhead.pdf".cyan()
+    ))
+}
+
+pub fn nup_usage() {
+    Printer::echoln(format!(
+        "The `{}` flag expects a grid (rows x columns) like `{}` or `{}`.",
+        "--nup".green(),
+        "2x1".cyan(),
+        "2x2".cyan()
+    ));
+    Printer::echoln(format!(
+        "The `{}` flag expects a paper name ({}) or a custom",
+        "--nup-paper".green(),
+        "a3, a4, a5, letter, legal, tabloid".cyan()
+    ));
+    Printer::echoln(format!(
+        "size like `{}`, and `{}` a non negative number (in",
+        "100x150mm".cyan(),
+        "--nup-gutter".green()
+    ));
+    Printer::echoln("points) leaving room for the grid cells on the paper.");
+    Printer::blankln(1);
+    Printer::echoln(format!(
+        "{}: `{}`",
+        "ie".green(),
+        "pdmers -i a.pdf b.pdf -o out.pdf --nup 2x2 --nup-paper letter".cyan()
+    ))
+}
+
+pub fn booklet_usage() {
+    Printer::echoln(format!(
+        "The `{}` and `{}` flags expect a non negative number",
+        "--booklet-gutter".green(),
+        "--booklet-creep".green()
+    ));
+    Printer::echoln(format!(
+        "(in points, the gutter narrower than the paper), `{}`",
+        "--booklet-signature".green()
+    ));
+    Printer::echoln("a positive number of sheets");
+    Printer::echoln(format!(
+        "and `{}` a paper name ({}) or a custom",
+        "--booklet-paper".green(),
+        "a3, a4, a5, letter, legal, tabloid".cyan()
+    ));
+    Printer::echoln(format!("size like `{}`.", "100x150mm".cyan()));
+    Printer::blankln(1);
+    Printer::echoln("The creep times the sheets nested in a signature (but the");
+    Printer::echoln("outer one) must stay within half the gutter, so the inner");
+    Printer::echoln("pages aren't shifted past the fold.");
+    Printer::blankln(1);
+    Printer::echoln(format!(
+        "{}: `{}`",
+        "ie".green(),
+        "pdmers -i a.pdf b.pdf -o out.pdf --booklet --booklet-signature 4".cyan()
+    ))
+}
+
+pub fn tile_usage() {
+    Printer::echoln(format!(
+        "The `{}` flag expects a paper name ({}) or a custom",
+        "--tile".green(),
+        "a3, a4, a5, letter, legal, tabloid".cyan()
+    ));
+    Printer::echoln(format!(
+        "size like `{}`. The `{}` and `{}` flags expect",
+        "100x150mm".cyan(),
+        "--tile-margin".green(),
+        "--tile-overlap".green()
+    ));
+    Printer::echoln("non negative numbers (in points) smaller than the paper.");
+    Printer::blankln(1);
+    Printer::echoln(format!(
+        "{}: `{}`",
+        "ie".green(),
+        "pdmers -i plan.pdf notes.pdf -o out.pdf --tile a4 --tile-overlap 18".cyan()
+    ))
+}
+
+pub fn page_size_usage() {
+    Printer::echoln(format!(
+        "The `{}` flag expects a paper name ({}) or a custom",
+        "--page-size".green(),
+        "a3, a4, a5, letter, legal, tabloid".cyan()
+    ));
+    Printer::echoln(format!(
+        "size like `{}`, and `{}` one of `{}`, `{}` or `{}`.",
+        "100x150mm".cyan(),
+        "--page-scale".green(),
+        "fit".cyan(),
+        "fill".cyan(),
+        "none".cyan()
+    ));
+    Printer::blankln(1);
+    Printer::echoln(format!(
+        "{}: `{}`",
+        "ie".green(),
+        "pdmers -i a.pdf b.pdf -o out.pdf --page-size a4 --page-scale fill".cyan()
+    ))
+}
+
+pub fn page_box_usage() {
+    Printer::echoln(format!(
+        "The `{}` flag expects margins (in points) like `{}`, `{}` or",
+        "--crop".green(),
+        "18".cyan(),
+        "18,36".cyan()
+    ));
+    Printer::echoln(format!(
+        "`{}` (top, right, bottom, left), or a box like `{}`.",
+        "18,36,18,36".cyan(),
+        "box:0,0,500,700".cyan()
+    ));
+    Printer::echoln(format!(
+        "The `{}` flag expects a non negative number (in points).",
+        "--add-margin".green()
+    ));
+    Printer::blankln(1);
+    Printer::echoln(format!(
+        "{}: `{}`",
+        "ie".green(),
+        "pdmers -i scan1.pdf scan2.pdf -o out.pdf --crop 12 --add-margin 36".cyan()
+    ))
+}
+
+/// Tips for `--encrypt` flags usage.
+pub fn encrypt_usage() {
+    Printer::echoln(format!(
+        "The `{}` flag expects `{}` or `{}`.",
+        "--encrypt-algorithm".green(),
+        "aes-256".cyan(),
+        "aes-128".cyan()
+    ));
+    Printer::echoln(format!(
+        "The `{}` and `{}` flags expect",
+        "--user-password".green(),
+        "--owner-password".green()
+    ));
+    Printer::echoln(format!(
+        "`{}` or `{}` (passwords aren't taken as is).",
+        "env:VAR".cyan(),
+        "file:PATH".cyan()
+    ));
+    Printer::echoln(format!(
+        "The `{}` flag expects a list like `{}`.",
+        "--deny".green(),
+        "print,copy,modify".cyan()
+    ));
+    Printer::blankln(1);
+    Printer::echoln(format!(
+        "{}: `{}`",
+        "ie".green(),
+        "pdmers -i a.pdf b.pdf -o out.pdf --encrypt --user-password env:PASS".cyan()
+    ))
+}
+
+pub fn sign_usage() {
+    Printer::echoln(format!(
+        "The `{}` flag expects an existing `{}` keystore",
+        "--sign".green(),
+        ".p12".cyan()
+    ));
+    Printer::echoln(format!(
+        "holding an RSA key (its passphrase is read from `{}`).",
+        "--sign-password-env".green()
+    ));
+    Printer::echoln(format!(
+        "The `{}` flag expects a position like `{}`",
+        "--sign-visible".green(),
+        "bottom-right".cyan()
+    ));
+    Printer::echoln(format!(
+        "and `{}` a page number within the output.",
+        "--sign-page".green()
+    ));
+    Printer::blankln(1);
+    Printer::echoln(format!(
+        "{}: `{}`",
+        "ie".green(),
+        "pdmers -i a.pdf b.pdf -o out.pdf --sign cert.p12 --sign-password-env P12_PASS".cyan()
+    ))
+}
+
+pub fn refused_signed_input() {
+    Printer::echoln(format!(
+        "`{}` stops when an input is digitally signed, since",
+        "--refuse-signed".green()
+    ));
+    Printer::echoln("merging would invalidate its signature. Either remove it");
+    Printer::echoln(format!(
+        "from the inputs or, when it's the first one, keep its signature with `{}`.",
+        "--keep-signature-of".green()
+    ));
+    Printer::blankln(1);
+    Printer::echoln(format!(
+        "{}: `{}`",
+        "ie".green(),
+        "pdmers -i signed.pdf annex.pdf -o out.pdf --keep-signature-of signed.pdf".cyan()
+    ))
+}
+
+pub fn signed_inputs() {
+    Printer::echoln(format!(
+        "The `{}` flag keeps the signature of the",
+        "--keep-signature-of".green()
+    ));
+    Printer::echoln("first input (not encrypted) by appending the other ones");
+    Printer::echoln("as an incremental update.");
+    Printer::blankln(1);
+    Printer::echoln(format!(
+        "{}: `{}`",
+        "ie".green(),
+        "pdmers -i signed.pdf annex.pdf -o out.pdf --keep-signature-of signed.pdf".cyan()
+    ))
+}
+
+pub fn resource_limits() {
+    Printer::echoln(format!(
+        "The `{}`, `{}`, `{}`,",
+        "--max-objects".green(),
+        "--max-stream-bytes".green(),
+        "--max-pages".green()
+    ));
+    Printer::echoln(format!(
+        "`{}` and `{}` flags expect a positive",
+        "--max-input-size".green(),
+        "--max-nesting".green()
+    ));
+    Printer::echoln(format!(
+        "integer (`{}` at most {}). Every input and stamp",
+        "--max-nesting".green(),
+        "128".cyan()
+    ));
+    Printer::echoln("must stay within them: raise the exceeded one only if");
+    Printer::echoln("the input is trusted.");
+    Printer::blankln(1);
+    Printer::echoln(format!(
+        "{}: `{}`",
+        "ie".green(),
+        "pdmers -i uploads -o out.pdf --max-pages 500 --max-input-size 50000000".cyan()
+    ))
+}
+
+pub fn root_usage() {
+    Printer::echoln(format!(
+        "The `{}` flag expects an existing directory every input",
+        "--root".green()
+    ));
+    Printer::echoln("(stamps, watermark image and order file included) and");
+    Printer::echoln("output must resolve inside, once their symlinks are");
+    Printer::echoln(format!(
+        "followed ({} references are allowed within it).",
+        "..".cyan()
+    ));
+    Printer::blankln(1);
+    Printer::echoln(format!(
+        "{}: `{}`",
+        "ie".green(),
+        "pdmers -i uploads -d 1 -o uploads/out.pdf --root uploads".cyan()
+    ))
+}